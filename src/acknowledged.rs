@@ -0,0 +1,120 @@
+// Acknowledged differences (known, accepted differences that should not fail a comparison)
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+
+// A region of a page (in pixels of the rendered page image) whose differences were reviewed and accepted
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AcknowledgedRegion {
+    pub page: i32,
+    pub top_left: [u32; 2],
+    pub bottom_right: [u32; 2],
+}
+
+
+// The structure of an acknowledged differences file
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Acknowledgements {
+    // How many pixels a differing chunk may extend past an acknowledged region and still be suppressed
+    #[serde(default = "default_tolerance")]
+    pub tolerance: u32,
+    pub acknowledged: Vec<AcknowledgedRegion>,
+}
+
+
+// A list of chunk coordinates (top left corner of each chunk)
+type Chunks = Vec<(u32, u32)>;
+
+
+fn default_tolerance() -> u32 {
+    10
+}
+
+
+impl Acknowledgements {
+
+    // Read and deserialize an acknowledged differences file.  The error message says what went wrong (for JSON, at which line and column).
+    pub fn load(path: &Path) -> Result<Acknowledgements, String> {
+        let mut content = String::new();
+
+        File::open(path).and_then(|mut file| file.read_to_string(&mut content))
+            .map_err(|e| format!("Failed to read the specified acknowledge file: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to deserialize JSON in acknowledge file: {}\
+            \nTips:\
+            \nVerify that the acknowledge file contains a valid JSON object with an 'acknowledged' list.\
+            \nCoordinates are in pixels of the rendered page image.", e))
+    }
+
+
+    // Check if the chunk at (x, y) on the given page falls within an acknowledged region (allowing for the tolerance)
+    pub fn is_acknowledged(&self, page: i32, x: u32, y: u32, chunk_size: u32) -> bool {
+        self.acknowledged.iter().any(|region| {
+            region.page == page &&
+            x + self.tolerance >= region.top_left[0] &&
+            y + self.tolerance >= region.top_left[1] &&
+            x + chunk_size <= region.bottom_right[0] + self.tolerance &&
+            y + chunk_size <= region.bottom_right[1] + self.tolerance
+        })
+    }
+
+
+    // Split the differing chunks of a page into (new differences, acknowledged differences)
+    pub fn split_chunks(&self, page: i32, chunks: Chunks, chunk_size: u32) -> (Chunks, Chunks) {
+        chunks.into_iter().partition(|&(x, y)| !self.is_acknowledged(page, x, y, chunk_size))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acknowledgements(tolerance: u32) -> Acknowledgements {
+        Acknowledgements {
+            tolerance,
+            acknowledged: vec![AcknowledgedRegion { page: 2, top_left: [100, 200], bottom_right: [300, 260] }],
+        }
+    }
+
+    #[test]
+    fn chunks_within_the_region_are_acknowledged() {
+        let acknowledgements = acknowledgements(0);
+
+        assert!(acknowledgements.is_acknowledged(2, 100, 200, 10));
+        assert!(acknowledgements.is_acknowledged(2, 290, 250, 10));
+
+        // The same place on another page, or a chunk reaching past the region, is not
+        assert!(!acknowledgements.is_acknowledged(1, 100, 200, 10));
+        assert!(!acknowledgements.is_acknowledged(2, 295, 250, 10));
+        assert!(!acknowledgements.is_acknowledged(2, 95, 200, 10));
+    }
+
+    #[test]
+    fn chunks_may_reach_past_the_region_by_the_tolerance() {
+        let acknowledgements = acknowledgements(10);
+
+        assert!(acknowledgements.is_acknowledged(2, 90, 190, 10));
+        assert!(acknowledgements.is_acknowledged(2, 300, 260, 10));
+        assert!(!acknowledgements.is_acknowledged(2, 89, 200, 10));
+        assert!(!acknowledgements.is_acknowledged(2, 301, 200, 10));
+    }
+
+    #[test]
+    fn acknowledged_region_is_suppressed_while_a_new_difference_still_counts() {
+        let (new_differences, acknowledged_differences) = acknowledgements(10).split_chunks(2, vec![(150, 220), (500, 500), (120, 240)], 10);
+
+        assert_eq!(new_differences, vec![(500, 500)]);
+        assert_eq!(acknowledged_differences, vec![(150, 220), (120, 240)]);
+    }
+
+    #[test]
+    fn tolerance_defaults_to_10_pixels() {
+        let acknowledgements: Acknowledgements = serde_json::from_str(r#"{"acknowledged": []}"#).unwrap();
+
+        assert_eq!(acknowledgements.tolerance, 10);
+    }
+}
@@ -0,0 +1,171 @@
+// Comparison of a PDF document against a folder of golden (previously approved) page images
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use image::imageops::FilterType;
+use image::RgbaImage;
+use log::debug;
+use pdfium_render::prelude::*;
+use crate::images;
+use crate::Config;
+
+
+// What was found when comparing the document against the golden images
+#[derive(Debug, Default)]
+pub struct BaselineReport {
+    pub differing_pages: Vec<i32>,
+    // Pages of the document that have no golden image (e.g. new pages)
+    pub missing_goldens: Vec<i32>,
+    // Golden images for pages that aren't in the document (e.g. removed pages)
+    pub orphan_goldens: Vec<i32>,
+}
+
+
+impl BaselineReport {
+
+    pub fn differences_found(&self) -> bool {
+        !self.differing_pages.is_empty() || !self.missing_goldens.is_empty() || !self.orphan_goldens.is_empty()
+    }
+
+    // The first page with any kind of difference
+    pub fn first_difference_page(&self) -> Option<i32> {
+        self.differing_pages.iter()
+            .chain(&self.missing_goldens)
+            .chain(&self.orphan_goldens)
+            .min()
+            .copied()
+    }
+}
+
+
+// Find the golden images in the folder, keyed by page number.  Golden images are named page-0001.png, page-0002.png, ...
+pub fn find_golden_images(golden_dir: &Path) -> std::io::Result<BTreeMap<i32, PathBuf>> {
+    let mut golden_images = BTreeMap::new();
+
+    for entry in std::fs::read_dir(golden_dir)? {
+        let path = entry?.path();
+
+        let page_number = path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("page-"))
+            .and_then(|name| name.strip_suffix(".png"))
+            .and_then(|number| number.parse::<i32>().ok());
+
+        if let Some(page_number) = page_number {
+            golden_images.insert(page_number, path);
+        }
+    }
+
+    Ok(golden_images)
+}
+
+
+// Compare each page of the document against its golden image
+pub fn compare_to_golden_images(
+    document: &PdfDocument,
+    golden_dir: &Path,
+    render_config: &PdfRenderConfig,
+    config: Option<&Config>,
+    settings: &images::ComparisonSettings,
+    filter: FilterType,
+) -> Result<BaselineReport, Box<dyn Error>> {
+    let pages = document.pages();
+
+    compare_pages_to_golden_images(pages.len(), golden_dir, config, settings, filter, |index| {
+        let page = pages.get(index)?;
+
+        Ok((images::render_page(&page, render_config)?, page.height().value.round() as i32))
+    })
+}
+
+
+// Compare each of the pages against its golden image.  A page is only rendered (by render, which gives the page image
+// and the height of the page in points) if it has a golden image.
+fn compare_pages_to_golden_images(
+    page_count: u16,
+    golden_dir: &Path,
+    config: Option<&Config>,
+    settings: &images::ComparisonSettings,
+    filter: FilterType,
+    mut render: impl FnMut(u16) -> Result<(RgbaImage, i32), Box<dyn Error>>,
+) -> Result<BaselineReport, Box<dyn Error>> {
+    let mut report = BaselineReport::default();
+    let mut golden_images = find_golden_images(golden_dir)?;
+
+    for index in 0..page_count {
+        let page_val = index as i32 + 1;
+
+        // Take this page's golden image out of the list, so the ones left over at the end are orphans
+        let Some(golden_path) = golden_images.remove(&page_val) else {
+            report.missing_goldens.push(page_val);
+            continue;
+        };
+
+        let (image1, height_in_points) = render(index)?;
+        let mut image2 = image::open(&golden_path)?.to_rgba8();
+
+        // The golden image may have been rendered at a different size
+        if image1.dimensions() != image2.dimensions() {
+            image2 = images::resize_image(&image2, image1.width(), image1.height(), filter);
+        }
+
+        let rectangles_to_ignore = crate::shapes_to_ignore(config, &settings.ignored_bands, page_val as u16, height_in_points, &image1);
+
+        let page_differences_vector = crate::compare_page_images(&image1, &image2, &rectangles_to_ignore, settings);
+
+        if !page_differences_vector.is_empty() {
+            debug!("page_differences_vector for page {:?}: {:?}", page_val, page_differences_vector);
+
+            report.differing_pages.push(page_val);
+        }
+    }
+
+    report.orphan_goldens = golden_images.into_keys().collect();
+
+    Ok(report)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // A page image with a black bar at the given height
+    fn page_with_bar(bar_y: u32) -> RgbaImage {
+        RgbaImage::from_fn(40, 60, |_, y| if (bar_y..bar_y + 5).contains(&y) { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) })
+    }
+
+    #[test]
+    fn document_is_compared_against_its_golden_images() {
+        let golden_dir = std::env::temp_dir().join(format!("match_pdf-baseline-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&golden_dir);
+        std::fs::create_dir_all(&golden_dir).unwrap();
+
+        // Page 1 is unchanged, page 2 has a different golden image, page 3 is new, and page 4 was removed
+        page_with_bar(10).save(golden_dir.join("page-0001.png")).unwrap();
+        page_with_bar(40).save(golden_dir.join("page-0002.png")).unwrap();
+        page_with_bar(10).save(golden_dir.join("page-0004.png")).unwrap();
+        std::fs::write(golden_dir.join("notes.txt"), "not a golden image").unwrap();
+
+        let mut rendered_pages = Vec::new();
+
+        let report = compare_pages_to_golden_images(3, &golden_dir, None, &images::ComparisonSettings::default(), FilterType::Triangle, |index| {
+            rendered_pages.push(index);
+
+            Ok((page_with_bar(10), 60))
+        }).unwrap();
+
+        std::fs::remove_dir_all(&golden_dir).unwrap();
+
+        assert_eq!(report.differing_pages, vec![2]);
+        assert_eq!(report.missing_goldens, vec![3]);
+        assert_eq!(report.orphan_goldens, vec![4]);
+        assert_eq!(report.first_difference_page(), Some(2));
+        assert!(report.differences_found());
+
+        // The new page has no golden image to compare with, so it isn't rendered
+        assert_eq!(rendered_pages, vec![0, 1]);
+    }
+}
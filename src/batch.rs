@@ -0,0 +1,118 @@
+// Batch related functions (compare two folders of PDF documents, pairing the documents by file name)
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::{check_folder, check_parent_folder, compare, Cli, ComparisonError};
+use log::{error, info};
+
+
+// The outcome for one file name of the batch
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BatchEntry {
+    pub file: String,
+    pub match_result: String,
+}
+
+
+// Compare every document of the first folder with the document of the same name in the second folder.
+// Each pair is compared as if it was given on the command line; an output folder gets one output file per pair.
+// Returns whether differences were found, counting documents that are only in one of the folders as differences.
+pub fn compare_folders(cli: &Cli) -> Result<bool, Box<dyn std::error::Error>> {
+    let folder1 = cli.original_pdf1_path.as_ref().expect("The first path is required.");
+    let folder2 = cli.original_pdf2_path.as_ref().expect("The second path is required.");
+
+    for folder in [folder1, folder2] {
+        if ! folder.is_dir() {
+            return Err(ComparisonError::new(format!("The provided folder {} does not exist or is not a folder.", folder.display())).into());
+        }
+    }
+
+    // In batch mode, the output and image paths are folders, with a file (or subfolder) for each pair
+    if let Some(ref path) = cli.output {
+        check_folder(path, "output", cli.create_dirs)?;
+    }
+
+    if let Some(ref path) = cli.result {
+        check_parent_folder(path, "result", cli.create_dirs)?;
+    }
+
+    let names1 = pdf_file_names(folder1)?;
+    let names2 = pdf_file_names(folder2)?;
+
+    let mut entries = Vec::new();
+    let mut errors = 0;
+
+    for name in names1.union(&names2) {
+        let match_result = match (names1.contains(name), names2.contains(name)) {
+            (true, true) => {
+                info!("Comparing {}...", name);
+
+                let stem = Path::new(name).file_stem().unwrap_or_default();
+
+                let mut pair_cli = cli.clone();
+                pair_cli.batch = false;
+                pair_cli.original_pdf1_path = Some(folder1.join(name));
+                pair_cli.original_pdf2_path = Some(folder2.join(name));
+                pair_cli.output = cli.output.as_ref().map(|dir| dir.join(name));
+                pair_cli.result = None;
+                pair_cli.gif_dir = cli.gif_dir.as_ref().map(|dir| dir.join(stem));
+                pair_cli.image_dir = cli.image_dir.as_ref().map(|dir| dir.join(stem));
+                pair_cli.create_dirs = cli.create_dirs || cli.gif_dir.is_some() || cli.image_dir.is_some();
+
+                match compare(pair_cli).map(|report| report.differences_found) {
+                    Ok(false) => "Documents match".to_string(),
+                    Ok(true) => "Differences were found".to_string(),
+                    Err(e) => {
+                        error!("Error comparing {}: {}", name, e);
+                        errors += 1;
+
+                        format!("Error: {}", e)
+                    }
+                }
+            },
+            (true, false) => "Only in the first folder".to_string(),
+            _ => "Only in the second folder".to_string(),
+        };
+
+        entries.push(BatchEntry { file: name.clone(), match_result });
+    }
+
+    // Summarize the batch
+    info!("");
+
+    for entry in &entries {
+        info!("{}: {}", entry.file, entry.match_result);
+    }
+
+    if let Some(ref path) = cli.result {
+        let mut file = File::create(path)?;
+        file.write_all(serde_json::to_string_pretty(&entries)?.as_bytes())?;
+    }
+
+    if errors > 0 {
+        return Err(format!("{} of the {} documents could not be compared.", errors, entries.len()).into());
+    }
+
+    Ok(entries.iter().any(|entry| entry.match_result != "Documents match"))
+}
+
+
+// The file names of the PDF documents in the folder (by extension, in any case)
+fn pdf_file_names(folder: &Path) -> std::io::Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_file() && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pdf")) {
+            if let Some(name) = path.file_name() {
+                names.insert(name.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Ok(names)
+}
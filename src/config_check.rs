@@ -0,0 +1,76 @@
+// Config file check related functions (check a config file on its own, without comparing any documents)
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use log::{error, info};
+use crate::{Cli, Config};
+
+
+// Check the config file and report what is in it and anything that is wrong with it.  Returns whether it is valid.
+// A field given twice in the same object (e.g. two 'page' values for a rectangle) is reported by the JSON parser.
+pub fn validate_config_file(path: &Path) -> bool {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("The config file {} can't be read: {}", path.display(), e);
+
+            return false;
+        }
+    };
+
+    let config: Config = match serde_json::from_str(&content) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("The config file {} is not valid: {}\n{}", path.display(), e, crate::CONFIG_FILE_TIPS);
+
+            return false;
+        }
+    };
+
+    info!("The config file {} holds rectangles: {}, circles: {}, polygons: {}, settings: {}.", path.display(),
+        config.ignored_rectangles.len(), config.ignored_circles.len(), config.ignored_polygons.len(),
+        if config.settings.is_some() { "yes" } else { "no" });
+
+    let mut problems: Vec<String> = Vec::new();
+
+    // Page values like "evenn" or "3-" never match a page, so their shapes are never used
+    let malformed_pages = config.malformed_page_references();
+
+    if !malformed_pages.is_empty() {
+        problems.push(format!("page values that are not understood (the shapes are skipped): {}", malformed_pages.join(", ")));
+    }
+
+    let mut seen_shapes = HashSet::new();
+
+    for shape in config.shapes() {
+        if shape.is_empty() {
+            problems.push(format!("page '{}': the {} is inverted or empty, so it excludes nothing", shape.page(), shape.describe()));
+        }
+
+        if !seen_shapes.insert((shape.page(), shape.describe())) {
+            problems.push(format!("page '{}': the {} is listed more than once", shape.page(), shape.describe()));
+        }
+    }
+
+    // The settings are checked as if no flags were given on the command line
+    if let Some(ref settings) = config.settings {
+        if let Err(message) = Cli::default().apply_settings(settings) {
+            problems.push(format!("the settings are not valid: {}", message));
+        }
+    }
+
+    if problems.is_empty() {
+        info!("The config file is valid.");
+
+        return true;
+    }
+
+    error!("The config file has {} problems:", problems.len());
+
+    for problem in &problems {
+        error!("  {}", problem);
+    }
+
+    false
+}
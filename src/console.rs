@@ -0,0 +1,147 @@
+// Console output related functions: the diagnostics go through the log crate, and this is its logger
+// (in quiet mode, standard output only gets the final verdict)
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::env;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+
+// Set from the 'quiet' flag, before anything is printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// Set when the command line sets up the console; a program using the library gets the verdict in the report instead
+static PRINT_VERDICT: AtomicBool = AtomicBool::new(false);
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+
+// Prints the messages of match_pdf itself as they are, without a prefix: warnings and errors to standard error,
+// the rest to standard output (to standard error as well in quiet mode, so standard output only has the verdict)
+struct ConsoleLogger;
+
+
+impl Log for ConsoleLogger {
+
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if record.level() <= Level::Warn || is_quiet() {
+            eprintln!("{}", record.args());
+        } else {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+
+// Set up the logger.  The level is taken from the RUST_LOG environment variable if it has a directive for match_pdf
+// (e.g. RUST_LOG=match_pdf=trace, or RUST_LOG=warn,match_pdf=debug) or a bare level (e.g. RUST_LOG=debug); otherwise it is
+// 'debug' with the 'debug' flag, 'warn' with the 'quiet' flag, and 'info' without either.
+// Only the command line sets it up; a program using the library keeps its own logger (and its level is left alone).
+pub fn init_logger(debug: bool, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    PRINT_VERDICT.store(!quiet, Ordering::Relaxed);
+
+    let level = env::var("RUST_LOG").ok()
+        .and_then(|value| level_from_directives(&value))
+        .unwrap_or(if debug {
+            LevelFilter::Debug
+        } else if quiet {
+            LevelFilter::Warn
+        } else {
+            LevelFilter::Info
+        });
+
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+
+// The level for match_pdf in comma-separated RUST_LOG directives: its own directive ('match_pdf=level', or just 'match_pdf'
+// for everything) wins over a bare level, and directives for other crates are skipped
+fn level_from_directives(value: &str) -> Option<LevelFilter> {
+    let mut own_level = None;
+    let mut bare_level = None;
+
+    for directive in value.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+        match directive.split_once('=') {
+            Some((target, level)) if target.trim() == env!("CARGO_CRATE_NAME") => {
+                own_level = LevelFilter::from_str(level.trim()).ok().or(own_level);
+            },
+            Some(_) => {},
+            None if directive == env!("CARGO_CRATE_NAME") => own_level = Some(LevelFilter::Trace),
+            None => bare_level = LevelFilter::from_str(directive).ok().or(bare_level),
+        }
+    }
+
+    own_level.or(bare_level)
+}
+
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+
+pub fn prints_verdict() -> bool {
+    PRINT_VERDICT.load(Ordering::Relaxed)
+}
+
+
+// Print the final verdict on the command line, unless in quiet mode (where MATCH or DIFF is printed instead).
+// It is not a log message, so it is printed whatever the log level.
+macro_rules! verdict {
+    ($($arg:tt)*) => {
+        if $crate::console::prints_verdict() {
+            println!($($arg)*);
+        }
+    };
+}
+
+
+pub(crate) use verdict;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_level_applies() {
+        assert_eq!(level_from_directives("debug"), Some(LevelFilter::Debug));
+        assert_eq!(level_from_directives(" WARN "), Some(LevelFilter::Warn));
+    }
+
+    #[test]
+    fn own_directive_wins_over_bare_level() {
+        assert_eq!(level_from_directives("match_pdf=trace"), Some(LevelFilter::Trace));
+        assert_eq!(level_from_directives("match_pdf=debug,warn"), Some(LevelFilter::Debug));
+        assert_eq!(level_from_directives("warn,match_pdf=error"), Some(LevelFilter::Error));
+        assert_eq!(level_from_directives("info,match_pdf"), Some(LevelFilter::Trace));
+    }
+
+    #[test]
+    fn other_crates_are_skipped() {
+        // Only the last token used to be read, which took the level meant for another crate
+        assert_eq!(level_from_directives("info,pdfium_render=trace"), Some(LevelFilter::Info));
+        assert_eq!(level_from_directives("match_pdf=warn,rayon=trace"), Some(LevelFilter::Warn));
+        assert_eq!(level_from_directives("rayon=trace"), None);
+    }
+
+    #[test]
+    fn unknown_levels_are_ignored() {
+        assert_eq!(level_from_directives("loud"), None);
+        assert_eq!(level_from_directives("match_pdf=loud,debug"), Some(LevelFilter::Debug));
+        assert_eq!(level_from_directives(""), None);
+    }
+}
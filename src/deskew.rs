@@ -0,0 +1,196 @@
+// Deskew related functions (correct small rotations of scanned pages)
+
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+
+// The largest skew (in degrees, either direction) that is looked for
+const MAX_SKEW_DEGREES: f64 = 5.0;
+
+// The step (in degrees) between the angles that are tried
+const SKEW_STEP_DEGREES: f64 = 0.1;
+
+// Skews smaller than this (in degrees) are left alone, as rotating would only blur the page
+const MIN_CORRECTED_SKEW_DEGREES: f64 = 0.05;
+
+
+// The skew that was detected (and corrected) on a page of each document
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PageSkew {
+    pub page: i32,
+    pub doc1_degrees: f64,
+    pub doc2_degrees: f64,
+}
+
+
+// Estimate how far (in degrees) the content of the page is rotated, using a projection profile:
+// the lines of a page are horizontal when the rows of dark pixels are most sharply separated,
+// i.e. when the sum of the squared dark pixel counts per row is highest.
+pub fn estimate_skew(image: &RgbaImage) -> f64 {
+    let (width, height) = image.dimensions();
+
+    // Collect the dark pixels, relative to the center of the page
+    let center_x = width as f64 / 2.0;
+    let center_y = height as f64 / 2.0;
+
+    let dark_pixels: Vec<(f64, f64)> = image.enumerate_pixels()
+        .filter(|(_, _, pixel)| (pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) < 3 * 128)
+        .map(|(x, y, _)| (x as f64 - center_x, y as f64 - center_y))
+        .collect();
+
+    if dark_pixels.is_empty() {
+        return 0.0;
+    }
+
+    // Rows can move off the page by up to half the diagonal when projected at an angle
+    let offset = ((width * width + height * height) as f64).sqrt() / 2.0;
+    let row_count = (offset * 2.0) as usize + 1;
+
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES).round() as i32;
+
+    let mut best_angle = 0.0;
+    let mut best_score = 0.0;
+
+    for step in -steps..=steps {
+        let angle = step as f64 * SKEW_STEP_DEGREES;
+        let tangent = angle.to_radians().tan();
+
+        // Count the dark pixels on each row, where the rows slope at the angle being tried
+        let mut rows = vec![0u32; row_count];
+
+        for &(x, y) in &dark_pixels {
+            let row = (y - x * tangent + offset) as usize;
+
+            if row < row_count {
+                rows[row] += 1;
+            }
+        }
+
+        let score: f64 = rows.iter().map(|&count| (count as f64) * (count as f64)).sum();
+
+        // Prefer the smallest angle when scores are equal
+        if score > best_score || (score == best_score && angle.abs() < f64::abs(best_angle)) {
+            best_score = score;
+            best_angle = angle;
+        }
+    }
+
+    best_angle
+}
+
+
+// Rotate the content of the image so content skewed by the given angle (in degrees, as estimated by estimate_skew) becomes level.
+// The image keeps its size; areas rotated in from outside the page are filled with white.
+pub fn rotate_to_level(image: &RgbaImage, skew_degrees: f64) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let mut new_image = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+
+    let (sine, cosine) = skew_degrees.to_radians().sin_cos();
+    let center_x = width as f64 / 2.0;
+    let center_y = height as f64 / 2.0;
+
+    for (x, y, pixel) in new_image.enumerate_pixels_mut() {
+        let relative_x = x as f64 - center_x;
+        let relative_y = y as f64 - center_y;
+
+        // Find where this pixel comes from in the skewed image
+        let source_x = relative_x * cosine - relative_y * sine + center_x;
+        let source_y = relative_x * sine + relative_y * cosine + center_y;
+
+        if let Some(source_pixel) = sample_bilinear(image, source_x, source_y) {
+            *pixel = source_pixel;
+        }
+    }
+
+    new_image
+}
+
+
+// Estimate the skew of the image and level it if needed.  Returns the image and the detected angle.
+pub fn deskew(image: RgbaImage) -> (RgbaImage, f64) {
+    let skew_degrees = estimate_skew(&image);
+
+    if skew_degrees.abs() < MIN_CORRECTED_SKEW_DEGREES {
+        (image, skew_degrees)
+    } else {
+        (rotate_to_level(&image, skew_degrees), skew_degrees)
+    }
+}
+
+
+// Sample the image between pixels, blending the four nearest pixels.  None if the point is outside the image.
+fn sample_bilinear(image: &RgbaImage, x: f64, y: f64) -> Option<Rgba<u8>> {
+    if x < 0.0 || y < 0.0 || x > (image.width() - 1) as f64 || y > (image.height() - 1) as f64 {
+        return None;
+    }
+
+    let left = x.floor() as u32;
+    let top = y.floor() as u32;
+    let right = (left + 1).min(image.width() - 1);
+    let bottom = (top + 1).min(image.height() - 1);
+
+    let fraction_x = x - left as f64;
+    let fraction_y = y - top as f64;
+
+    let top_left = image.get_pixel(left, top);
+    let top_right = image.get_pixel(right, top);
+    let bottom_left = image.get_pixel(left, bottom);
+    let bottom_right = image.get_pixel(right, bottom);
+
+    let mut blended = Rgba([0, 0, 0, 0]);
+
+    for channel in 0..4 {
+        let upper = top_left[channel] as f64 * (1.0 - fraction_x) + top_right[channel] as f64 * fraction_x;
+        let lower = bottom_left[channel] as f64 * (1.0 - fraction_x) + bottom_right[channel] as f64 * fraction_x;
+
+        blended[channel] = (upper * (1.0 - fraction_y) + lower * fraction_y).round() as u8;
+    }
+
+    Some(blended)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A white page with lines of "text": dark horizontal bars
+    fn page_with_lines() -> RgbaImage {
+        RgbaImage::from_fn(400, 400, |x, y| {
+            if (60..340).contains(&x) && (60..340).contains(&y) && y % 30 < 8 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        })
+    }
+
+    // The number of pixels that are dark in one image and light in the other
+    fn mismatched_pixels(image1: &RgbaImage, image2: &RgbaImage) -> usize {
+        image1.pixels().zip(image2.pixels()).filter(|(pixel1, pixel2)| (pixel1[0] < 128) != (pixel2[0] < 128)).count()
+    }
+
+    #[test]
+    fn level_page_has_no_skew() {
+        assert_eq!(estimate_skew(&page_with_lines()), 0.0);
+        assert_eq!(estimate_skew(&RgbaImage::from_pixel(50, 50, Rgba([255, 255, 255, 255]))), 0.0);
+    }
+
+    #[test]
+    fn skewed_copy_matches_the_original_after_deskew_but_not_without_it() {
+        let original = page_with_lines();
+
+        // Skew the page by 2 degrees (the opposite of leveling it)
+        let skewed = rotate_to_level(&original, -2.0);
+        let (deskewed, skew_degrees) = deskew(skewed.clone());
+
+        assert!((skew_degrees.abs() - 2.0).abs() <= 1.5 * SKEW_STEP_DEGREES, "detected {} degrees", skew_degrees);
+
+        // Only the edges of the bars, blurred by rotating twice, may still differ
+        let mismatched_before = mismatched_pixels(&original, &skewed);
+        let mismatched_after = mismatched_pixels(&original, &deskewed);
+
+        assert!(mismatched_before > 5000, "{} pixels differ before deskewing", mismatched_before);
+        assert!(mismatched_after * 20 < mismatched_before, "{} pixels still differ after deskewing", mismatched_after);
+    }
+}
@@ -0,0 +1,136 @@
+// Form field related functions (compare the values of the fields of filled in forms, regardless of how they are rendered)
+
+use std::collections::BTreeMap;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+// How a form field differs between the documents
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldChange {
+    Changed,
+    Added,
+    Removed,
+}
+
+
+// A form field whose value differs between the documents
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FieldDifference {
+    pub name: String,
+    pub change: FieldChange,
+    // The value in each document (None if the field isn't there or has no value)
+    pub value1: Option<String>,
+    pub value2: Option<String>,
+}
+
+
+// Collect the value of every named form field in the document, keyed by field name.
+// Checkboxes and radio buttons that share a name form a group; the value of the group is the value of its checked button.
+pub fn field_values(document: &PdfDocument) -> BTreeMap<String, Option<String>> {
+    let mut values = BTreeMap::new();
+
+    for page in document.pages().iter() {
+        for annotation in page.annotations().iter() {
+            let Some(field) = annotation.as_form_field() else {
+                continue;
+            };
+
+            let Some(name) = field.name() else {
+                continue;
+            };
+
+            let checked_value = match field.field_type() {
+                PdfFormFieldType::Checkbox => field.as_checkbox_field()
+                    .map(|checkbox| (checkbox.is_checked().unwrap_or(false), checkbox.group_value())),
+                PdfFormFieldType::RadioButton => field.as_radio_button_field()
+                    .map(|radio_button| (radio_button.is_checked().unwrap_or(false), radio_button.group_value())),
+                _ => None,
+            };
+
+            match checked_value {
+                // A checked button sets the value of its group
+                Some((true, group_value)) => {
+                    values.insert(name, Some(group_value.unwrap_or_else(|| "checked".to_string())));
+                },
+                // An unchecked button only shows the group is there
+                Some((false, _)) => {
+                    values.entry(name).or_insert(None);
+                },
+                None => {
+                    let value = match field.field_type() {
+                        PdfFormFieldType::Text => field.as_text_field().and_then(|text| text.value()),
+                        PdfFormFieldType::ComboBox => field.as_combo_box_field().and_then(|combo_box| combo_box.value()),
+                        PdfFormFieldType::ListBox => field.as_list_box_field().and_then(|list_box| list_box.value()),
+                        _ => None,
+                    };
+
+                    values.insert(name, value);
+                },
+            }
+        }
+    }
+
+    values
+}
+
+
+// Compare the form field values of the two documents, in order of field name
+pub fn compare_field_values(values1: &BTreeMap<String, Option<String>>, values2: &BTreeMap<String, Option<String>>) -> Vec<FieldDifference> {
+    let mut names: Vec<&String> = values1.keys().chain(values2.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names.into_iter()
+        .filter_map(|name| {
+            let change = match (values1.get(name), values2.get(name)) {
+                (Some(value1), Some(value2)) if value1 == value2 => return None,
+                (Some(_), Some(_)) => FieldChange::Changed,
+                (None, _) => FieldChange::Added,
+                (_, None) => FieldChange::Removed,
+            };
+
+            Some(FieldDifference {
+                name: name.clone(),
+                change,
+                value1: values1.get(name).cloned().flatten(),
+                value2: values2.get(name).cloned().flatten(),
+            })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(fields: &[(&str, Option<&str>)]) -> BTreeMap<String, Option<String>> {
+        fields.iter().map(|(name, value)| (name.to_string(), value.map(str::to_string))).collect()
+    }
+
+    #[test]
+    fn only_the_field_with_a_different_value_is_reported() {
+        let values1 = values(&[("name", Some("Ada")), ("city", Some("London")), ("agree", Some("Yes"))]);
+        let values2 = values(&[("name", Some("Ada")), ("city", Some("Paris")), ("agree", Some("Yes"))]);
+
+        let differences = compare_field_values(&values1, &values2);
+
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].name, "city");
+        assert_eq!(differences[0].change, FieldChange::Changed);
+        assert_eq!((differences[0].value1.as_deref(), differences[0].value2.as_deref()), (Some("London"), Some("Paris")));
+    }
+
+    #[test]
+    fn fields_in_one_document_only_are_added_or_removed() {
+        // An unchecked group (no value) still counts as being there
+        let values1 = values(&[("agree", None), ("old", Some("x"))]);
+        let values2 = values(&[("agree", None), ("new", Some("y"))]);
+
+        let changes: Vec<(String, FieldChange)> = compare_field_values(&values1, &values2).into_iter().map(|difference| (difference.name, difference.change)).collect();
+
+        assert_eq!(changes, vec![("new".to_string(), FieldChange::Added), ("old".to_string(), FieldChange::Removed)]);
+    }
+}
@@ -0,0 +1,186 @@
+// Geometry related functions (conversions between inches, points and pixels)
+//
+// Config rectangles are given in inches, PDF pages are measured in points, and page images in pixels.
+// The number of pixels per point depends on the size of the page and the resolution it was rendered at,
+// so it is calculated from the height of the page in points and the height of its image in pixels.
+
+
+// PDF documents use points as a unit of measurement, and there are 72 points to an inch.
+pub const POINTS_PER_INCH: f64 = 72.0;
+
+// PDF pages can be at most 200 inches (14400 points) in either direction
+pub const MAX_PAGE_SIZE_IN_POINTS: f64 = 14400.0;
+
+// Width of an output page in inches, unless another width is asked for
+pub const DEFAULT_OUTPUT_WIDTH_IN_INCHES: f64 = 17.0;
+
+
+// The number of pixels per point of a rendered page: image height in pixels / page height in points
+pub fn pixels_per_point(height_in_pixels: u32, height_in_points: f64) -> f64 {
+    height_in_pixels as f64 / height_in_points
+}
+
+
+// The number of pixels per inch of a rendered page
+pub fn pixels_per_inch(pixels_per_point: f64) -> f64 {
+    pixels_per_point * POINTS_PER_INCH
+}
+
+
+pub fn points_to_pixels(points: f64, pixels_per_point: f64) -> f64 {
+    points * pixels_per_point
+}
+
+
+pub fn pixels_to_points(pixels: f64, pixels_per_point: f64) -> f64 {
+    pixels / pixels_per_point
+}
+
+
+pub fn inches_to_pixels(inches: f64, pixels_per_point: f64) -> f64 {
+    points_to_pixels(inches * POINTS_PER_INCH, pixels_per_point)
+}
+
+
+pub fn pixels_to_inches(pixels: f64, pixels_per_point: f64) -> f64 {
+    pixels_to_points(pixels, pixels_per_point) / POINTS_PER_INCH
+}
+
+
+// Calculate the factor that scales a combined image (in pixels) to the width of an output page (in points)
+pub fn output_scale_factor(combined_image_width: u32, output_width_in_inches: f64) -> f32 {
+    (output_width_in_inches * POINTS_PER_INCH / combined_image_width as f64) as f32
+}
+
+
+// Parse the width of an output page (in inches) given on the command line: at least 1 inch, and at most the largest PDF page size
+pub fn parse_output_width(value: &str) -> Result<f64, String> {
+    let max_inches = MAX_PAGE_SIZE_IN_POINTS / POINTS_PER_INCH;
+
+    match value.trim().parse::<f64>() {
+        Ok(inches) if (1.0..=max_inches).contains(&inches) => Ok(inches),
+        Ok(_) => Err(format!("the width has to be between 1 and {} inches", max_inches)),
+        Err(_) => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+
+// Parse a resolution (in DPI) given on the command line: more than 0
+pub fn parse_dpi(value: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(dpi) if dpi > 0.0 && dpi.is_finite() => Ok(dpi),
+        Ok(_) => Err("the resolution has to be more than 0 DPI".to_string()),
+        Err(_) => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+
+// Parse the tolerance (in points) within which page sizes are treated as equal: 0 or more
+pub fn parse_size_epsilon(value: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(points) if points >= 0.0 && points.is_finite() => Ok(points),
+        Ok(_) => Err("the tolerance has to be 0 points or more".to_string()),
+        Err(_) => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+
+// Check if two page sizes (in points) differ by more than the tolerance in either direction
+pub fn page_sizes_differ(size1: (f32, f32), size2: (f32, f32), epsilon: f32) -> bool {
+    (size1.0 - size2.0).abs() > epsilon || (size1.1 - size2.1).abs() > epsilon
+}
+
+
+// Reduce the scale factor if needed, so the output page stays within the largest page size a PDF document allows
+pub fn limit_scale_to_pdf_page_size(scale_factor: f32, combined_image_width: u32, combined_image_height: u32) -> f32 {
+    let largest_side = combined_image_width.max(combined_image_height) as f64;
+
+    scale_factor.min((MAX_PAGE_SIZE_IN_POINTS / largest_side) as f32)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn inches_points_and_pixels_round_trip() {
+        let pixels_per_point = pixels_per_point(2588, 792.0);
+
+        for value in [0.0, 0.25, 1.0, 8.5, 11.0, 199.5] {
+            assert!((pixels_to_inches(inches_to_pixels(value, pixels_per_point), pixels_per_point) - value).abs() < EPSILON);
+            assert!((pixels_to_points(points_to_pixels(value, pixels_per_point), pixels_per_point) - value).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn one_inch_page_rendered_at_2000_pixels() {
+        // A page 72 points (one inch) high, rendered 2000 pixels high
+        let pixels_per_point = pixels_per_point(2000, 72.0);
+
+        assert!((pixels_per_point - 2000.0 / 72.0).abs() < EPSILON);
+        assert!((pixels_per_inch(pixels_per_point) - 2000.0).abs() < EPSILON);
+        assert!((inches_to_pixels(1.0, pixels_per_point) - 2000.0).abs() < EPSILON);
+        assert!((points_to_pixels(72.0, pixels_per_point) - 2000.0).abs() < EPSILON);
+        assert!((pixels_to_points(1000.0, pixels_per_point) - 36.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn letter_page_rendered_2000_pixels_wide() {
+        // A letter page (8.5 x 11 inches, 612 x 792 points) rendered 2000 pixels wide is 2588 pixels high
+        let pixels_per_point = pixels_per_point(2588, 792.0);
+
+        assert!((inches_to_pixels(8.5, pixels_per_point) - 2000.0).abs() < 1.0);
+        assert!((points_to_pixels(792.0, pixels_per_point) - 2588.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn output_page_fits_the_largest_pdf_page() {
+        assert!((output_scale_factor(4005, 17.0) as f64 * 4005.0 - 17.0 * POINTS_PER_INCH).abs() < 0.01);
+        assert_eq!(limit_scale_to_pdf_page_size(1.0, 100, 200), 1.0);
+        assert!(limit_scale_to_pdf_page_size(1.0, 100, 28800) <= 0.5);
+    }
+
+    #[test]
+    fn dpi_has_to_be_positive() {
+        assert_eq!(parse_dpi("150"), Ok(150.0));
+        assert_eq!(parse_dpi(" 72.5 "), Ok(72.5));
+        assert!(parse_dpi("0").is_err());
+        assert!(parse_dpi("-300").is_err());
+        assert!(parse_dpi("inf").is_err());
+        assert!(parse_dpi("high").is_err());
+    }
+
+    #[test]
+    fn rectangles_line_up_on_pages_rendered_at_different_dpi() {
+        // A letter page (792 points high) rendered at 72 DPI and at 144 DPI: each image has its own pixels per point
+        let pixels_per_point_72 = pixels_per_point(792, 792.0);
+        let pixels_per_point_144 = pixels_per_point(1584, 792.0);
+
+        // A rectangle 2 inches from the top lands at the same place once the 72 DPI image is enlarged to the 144 DPI grid
+        assert!((inches_to_pixels(2.0, pixels_per_point_72) * 2.0 - inches_to_pixels(2.0, pixels_per_point_144)).abs() < EPSILON);
+        assert!((inches_to_pixels(2.0, pixels_per_point_144) - 288.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn page_sizes_within_the_tolerance_are_equal() {
+        // A letter page and the same page 0.3 points wider
+        let letter = (612.0, 792.0);
+        let wider = (612.3, 792.0);
+
+        assert!(!page_sizes_differ(letter, wider, 0.5));
+        assert!(page_sizes_differ(letter, wider, 0.1));
+        assert!(page_sizes_differ(letter, (612.0, 792.3), 0.1));
+        assert!(!page_sizes_differ(letter, letter, 0.0));
+    }
+
+    #[test]
+    fn size_tolerance_cannot_be_negative() {
+        assert_eq!(parse_size_epsilon("0"), Ok(0.0));
+        assert_eq!(parse_size_epsilon("0.5"), Ok(0.5));
+        assert!(parse_size_epsilon("-0.1").is_err());
+        assert!(parse_size_epsilon("wide").is_err());
+    }
+}
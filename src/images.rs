@@ -2,10 +2,19 @@
 
 use pdfium_render::prelude::*;
 use image::RgbaImage;
+use image::imageops::FilterType;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
 use image::{ImageBuffer, Rgba};
-// Use the Rectangle struct in main.rs
-use crate::Rectangle;
+use rayon::prelude::*;
+// Use the exclusion zones of the config file
+use crate::shapes::{IgnoreShape, IgnoreShapes, PageBands};
 
+// The width and height (in pixels) of the square chunks used to compare images
+pub const CHUNK_SIZE: u32 = 10;
 
 /// Given a pdf document object (loaded pdf) and page number, return an image of the page
 pub fn render_page(page: &PdfPage, render_config: &PdfRenderConfig) -> Result<RgbaImage, PdfiumError> {
@@ -19,18 +28,347 @@ pub fn render_page(page: &PdfPage, render_config: &PdfRenderConfig) -> Result<Rg
 }
 
 
+// Remove every object that isn't of the given type from the page, so only that type of object is rendered.
+// The changes are never committed, so the document itself is not changed.
+// Note: objects inside form XObjects are removed along with the form XObject, unless forms are the chosen type.
+pub fn keep_only_objects_of_type(page: &mut PdfPage, object_type: PdfPageObjectType) -> Result<(), PdfiumError> {
+    page.set_content_regeneration_strategy(PdfPageContentRegenerationStrategy::Manual);
+
+    let objects = page.objects_mut();
+    let object_types = objects.iter().map(|object| object.object_type()).collect::<Vec<_>>();
+
+    for index in objects_to_remove(&object_types, object_type) {
+        objects.remove_object_at_index(index)?;
+    }
+
+    Ok(())
+}
+
+
+// The indices of the objects (given by their types) that are not of the type to keep, from the last to the first,
+// so removing an object doesn't change the index of the objects still to be removed
+fn objects_to_remove(object_types: &[PdfPageObjectType], keep: PdfPageObjectType) -> Vec<usize> {
+    (0..object_types.len()).rev()
+        .filter(|&index| object_types[index] != keep)
+        .collect()
+}
+
+
+// Render a page from each document, making sure both images have the same dimensions.
+// The documents may have been rendered at different resolutions (or rounding in the renderer can leave them a pixel apart),
+// so the smaller image is resampled onto the grid of the larger one.
+pub fn render_page_pair(
+    page1: &PdfPage,
+    page2: &PdfPage,
+    render_config1: &PdfRenderConfig,
+    render_config2: &PdfRenderConfig,
+    filter: FilterType,
+) -> Result<(RgbaImage, RgbaImage), PdfiumError> {
+    let image1 = render_page(page1, render_config1)?;
+    let image2 = render_page(page2, render_config2)?;
+
+    Ok(match_image_sizes(image1, image2, filter))
+}
+
+
+// Page images that differ by at most this many pixels in width and height (e.g. from rounding when rendering) are padded, not resampled
+const MAX_PADDED_SIZE_DIFFERENCE: u32 = 2;
+
+
+// Make sure both page images have the same dimensions.
+// Images that differ by a pixel or two are padded with white to the larger size, as resampling would blur every pixel of the page;
+// otherwise the smaller image is resampled onto the grid of the larger one.
+pub fn match_image_sizes(mut image1: RgbaImage, mut image2: RgbaImage, filter: FilterType) -> (RgbaImage, RgbaImage) {
+    if image1.dimensions() != image2.dimensions() {
+        if image1.width().abs_diff(image2.width()) <= MAX_PADDED_SIZE_DIFFERENCE && image1.height().abs_diff(image2.height()) <= MAX_PADDED_SIZE_DIFFERENCE {
+            let (width, height) = (image1.width().max(image2.width()), image1.height().max(image2.height()));
+
+            image1 = pad_image(&image1, width, height);
+            image2 = pad_image(&image2, width, height);
+        } else if image1.width() * image1.height() >= image2.width() * image2.height() {
+            image2 = resize_image(&image2, image1.width(), image1.height(), filter);
+        } else {
+            image1 = resize_image(&image1, image2.width(), image2.height(), filter);
+        }
+    }
+
+    (image1, image2)
+}
+
+
+// Extend an image to the given dimensions, filling the new pixels on the right and at the bottom with white
+fn pad_image(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    if image.dimensions() == (width, height) {
+        return image.clone();
+    }
+
+    let mut padded_image = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+    image::imageops::replace(&mut padded_image, image, 0, 0);
+
+    padded_image
+}
+
+
+// Resize an image to the given dimensions, using the given resampling filter
+pub fn resize_image(image: &RgbaImage, width: u32, height: u32, filter: FilterType) -> RgbaImage {
+    image::imageops::resize(image, width, height, filter)
+}
+
+
+// How strongly the lightest tones are stretched when amplifying faint marks
+const FAINT_AMPLIFICATION: u32 = 8;
+
+
+// Convert an image to grayscale and stretch the contrast of the light tones,
+// so faint marks (e.g. light gray ink on white paper) become clearly visible differences
+pub fn amplify_faint(image: &RgbaImage) -> RgbaImage {
+    let mut new_image = image.clone();
+
+    for pixel in new_image.pixels_mut() {
+        // Luma (ITU-R BT.601 weights)
+        let luma = (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+
+        // Stretch the distance from white, so the lightest band of tones covers the full range
+        let amplified = 255 - ((255 - luma) * FAINT_AMPLIFICATION).min(255);
+
+        pixel[0] = amplified as u8;
+        pixel[1] = amplified as u8;
+        pixel[2] = amplified as u8;
+    }
+
+    new_image
+}
+
+
+// The largest difference (per color channel) between the background colors of two pages that is treated as a shift in tone
+const MAX_BACKGROUND_SHIFT: i16 = 8;
+
+
+// The most common color of the image, which is the background color for most pages
+pub fn modal_color(image: &RgbaImage) -> Rgba<u8> {
+    let mut counts: HashMap<[u8; 4], u32> = HashMap::new();
+
+    for pixel in image.pixels() {
+        *counts.entry(pixel.0).or_insert(0) += 1;
+    }
+
+    counts.into_iter()
+        .max_by_key(|&(color, count)| (count, color))
+        .map_or(Rgba([255, 255, 255, 255]), |(color, _)| Rgba(color))
+}
+
+
+// The difference (per color channel, first image minus second) between the background colors of the images,
+// if it's small enough to be a shift in tone (e.g. 254,254,254 instead of 255,255,255).  None if the backgrounds are the same or too different.
+pub fn background_shift(image1: &RgbaImage, image2: &RgbaImage) -> Option<[i16; 3]> {
+    let background1 = modal_color(image1);
+    let background2 = modal_color(image2);
+
+    let shift = [0, 1, 2].map(|channel| background1[channel] as i16 - background2[channel] as i16);
+
+    if shift == [0, 0, 0] || shift.iter().any(|difference| difference.abs() > MAX_BACKGROUND_SHIFT) {
+        None
+    } else {
+        Some(shift)
+    }
+}
+
+
+// Shift the colors of every pixel of the image by the given amount per channel
+pub fn shift_colors(image: &RgbaImage, shift: [i16; 3]) -> RgbaImage {
+    let mut new_image = image.clone();
+
+    for pixel in new_image.pixels_mut() {
+        for channel in 0..3 {
+            pixel[channel] = (pixel[channel] as i16 + shift[channel]).clamp(0, 255) as u8;
+        }
+    }
+
+    new_image
+}
+
+
+// How far (summed over the color channels) a pixel can be from the background color and still be background
+const BACKGROUND_DISTANCE: u32 = 48;
+
+
+// How a differing chunk changed, judged by which image has content (pixels other than the background) in the chunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChunkChange {
+    // Content only in the first image
+    Removed,
+    // Content only in the second image
+    Added,
+    // Content in both images (or in neither)
+    Changed,
+}
+
+
+// Classify each of the differing chunks as removed, added or changed content
+pub fn classify_chunks(img1: &RgbaImage, img2: &RgbaImage, chunks: &[(u32, u32)]) -> Vec<ChunkChange> {
+    if chunks.is_empty() {
+        return Vec::new();
+    }
+
+    let background1 = modal_color(img1);
+    let background2 = modal_color(img2);
+
+    chunks.iter()
+        .map(|&(x, y)| match (chunk_has_content(img1, x, y, &background1), chunk_has_content(img2, x, y, &background2)) {
+            (true, false) => ChunkChange::Removed,
+            (false, true) => ChunkChange::Added,
+            _ => ChunkChange::Changed,
+        })
+        .collect()
+}
+
+
+// Check if any pixel of the chunk is clearly not the background color
+fn chunk_has_content(image: &RgbaImage, x: u32, y: u32, background: &Rgba<u8>) -> bool {
+    let (width, height) = image.dimensions();
+
+    (y..(y + CHUNK_SIZE).min(height)).any(|actual_y| {
+        (x..(x + CHUNK_SIZE).min(width)).any(|actual_x| {
+            let pixel = image.get_pixel(actual_x, actual_y);
+
+            (0..3).map(|channel| (pixel[channel] as i32 - background[channel] as i32).unsigned_abs()).sum::<u32>() > BACKGROUND_DISTANCE
+        })
+    })
+}
+
+
+// Check if the page has no content: every pixel is close to the background color (as in chunk_has_content)
+pub fn is_blank(image: &RgbaImage) -> bool {
+    let background = modal_color(image);
+
+    image.pixels().all(|pixel| {
+        (0..3).map(|channel| (pixel[channel] as i32 - background[channel] as i32).unsigned_abs()).sum::<u32>() <= BACKGROUND_DISTANCE
+    })
+}
+
+
+// Settings that control how strictly two images are compared
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComparisonSettings {
+    // A differing pixel is treated as unchanged if a matching pixel exists within this many pixels in the other image (0 = exact position)
+    pub neighborhood: u32,
+    // Compare grayscale copies of the images with faint marks amplified (see amplify_faint)
+    pub amplify_faint: bool,
+    // If set, a chunk only differs when the center-weighted sum of its differing pixels is over this threshold (see center_weight)
+    pub center_weighted_threshold: Option<f64>,
+    // Two pixels are equal if each of their channels differs by at most this much (0 = exact match)
+    pub tolerance: u8,
+    // Leave the alpha channel out when comparing pixels
+    pub ignore_alpha: bool,
+    // If set, compare chunks by the mean squared error of their pixels, and only flag chunks over this threshold (see chunk_mean_squared_error)
+    pub mse_threshold: Option<f64>,
+    // Shift the colors of the second image to the background of the first, if their backgrounds differ slightly (see background_shift)
+    pub normalize_background: bool,
+    // A differing chunk is treated as unchanged if it matches the other image shifted by up to this many pixels (see chunk_matches_shifted)
+    pub shift_tolerance: u32,
+    // The bands at the top and bottom of every page that are ignored, like the shapes of a config file (see shapes_to_ignore in main.rs)
+    pub ignored_bands: PageBands,
+}
+
+
+impl ComparisonSettings {
+
+    // Check if two pixels are equal, within the tolerance
+    pub fn pixels_match(&self, pixel1: &Rgba<u8>, pixel2: &Rgba<u8>) -> bool {
+        let channels = if self.ignore_alpha { 3 } else { 4 };
+
+        // Work in u16, so the difference between two u8 values can't overflow
+        (0..channels).all(|channel| (pixel1[channel] as u16).abs_diff(pixel2[channel] as u16) <= self.tolerance as u16)
+    }
+}
+
+
+// Map differing chunks found on an image of one size onto the chunk grid of an image of another size (e.g. the chunks found
+// when confirming at a higher resolution, back onto the standard resolution).  Several chunks can land in the same chunk,
+// which is then listed once, in the order compare_images_in_chunks lists chunks (row by row).
+pub fn scale_chunks(chunks: &[(u32, u32)], from_size: (u32, u32), to_size: (u32, u32)) -> Vec<(u32, u32)> {
+    let snap = |value: u32, from: u32, to: u32| (value as u64 * to as u64 / from as u64) as u32 / CHUNK_SIZE * CHUNK_SIZE;
+
+    let mut scaled_chunks: Vec<(u32, u32)> = chunks.iter()
+        .map(|&(x, y)| (snap(x, from_size.0, to_size.0), snap(y, from_size.1, to_size.1)))
+        .collect();
+
+    scaled_chunks.sort_by_key(|&(x, y)| (y, x));
+    scaled_chunks.dedup();
+
+    scaled_chunks
+}
+
+
+// Count the chunks of an image that are compared, i.e. that are not completely inside an ignore rectangle
+pub fn count_compared_chunks(width: u32, height: u32, ignore_rects: &[Box<dyn IgnoreShape>]) -> usize {
+    (0..height).step_by(CHUNK_SIZE as usize)
+        .flat_map(|y| (0..width).step_by(CHUNK_SIZE as usize).map(move |x| (x, y)))
+        .filter(|&(x, y)| !ignore_rects.iter().any(|rect| rect.covers_chunk(x, y, CHUNK_SIZE)))
+        .count()
+}
+
+
+// The structural similarity index (SSIM) of two images, from 1.0 (identical) down towards 0.0 (unrelated) or below.
+// It is the average SSIM of the luma of each chunk, leaving out the chunks that are completely inside an ignore rectangle.
+// None if every chunk is ignored.
+pub fn compute_ssim(img1: &RgbaImage, img2: &RgbaImage, ignore_rects: &[Box<dyn IgnoreShape>]) -> Option<f64> {
+    // The usual stabilizing constants, for 8-bit values
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (width, height) = img1.dimensions();
+    let luma = |pixel: &Rgba<u8>| pixel[0] as f64 * 0.299 + pixel[1] as f64 * 0.587 + pixel[2] as f64 * 0.114;
+
+    let chunks: Vec<(u32, u32)> = (0..height).step_by(CHUNK_SIZE as usize)
+        .flat_map(|y| (0..width).step_by(CHUNK_SIZE as usize).map(move |x| (x, y)))
+        .filter(|&(x, y)| !ignore_rects.iter().any(|rect| rect.covers_chunk(x, y, CHUNK_SIZE)))
+        .collect();
+
+    if chunks.is_empty() {
+        return None;
+    }
+
+    let total: f64 = chunks.par_iter().map(|&(x, y)| {
+        let pixels: Vec<(f64, f64)> = (y..(y + CHUNK_SIZE).min(height))
+            .flat_map(|actual_y| (x..(x + CHUNK_SIZE).min(width)).map(move |actual_x| (actual_x, actual_y)))
+            .map(|(actual_x, actual_y)| (luma(img1.get_pixel(actual_x, actual_y)), luma(img2.get_pixel(actual_x, actual_y))))
+            .collect();
+
+        let count = pixels.len() as f64;
+        let mean1 = pixels.iter().map(|pixel| pixel.0).sum::<f64>() / count;
+        let mean2 = pixels.iter().map(|pixel| pixel.1).sum::<f64>() / count;
+        let variance1 = pixels.iter().map(|pixel| (pixel.0 - mean1).powi(2)).sum::<f64>() / count;
+        let variance2 = pixels.iter().map(|pixel| (pixel.1 - mean2).powi(2)).sum::<f64>() / count;
+        let covariance = pixels.iter().map(|pixel| (pixel.0 - mean1) * (pixel.1 - mean2)).sum::<f64>() / count;
+
+        ((2.0 * mean1 * mean2 + C1) * (2.0 * covariance + C2)) /
+            ((mean1 * mean1 + mean2 * mean2 + C1) * (variance1 + variance2 + C2))
+    }).sum();
+
+    Some(total / chunks.len() as f64)
+}
+
+
 pub fn compare_images_in_chunks(
     img1: &ImageBuffer<Rgba<u8>, Vec<u8>>,
     img2: &ImageBuffer<Rgba<u8>, Vec<u8>>,
-    ignore_rects: Option<&Vec<Rectangle>>,
+    ignore_rects: Option<&IgnoreShapes>,
+    settings: &ComparisonSettings,
 ) -> Vec<(u32, u32)> {
-    let chunk_size = 10;
-    let mut differing_chunks = vec![];
+    let chunk_size = CHUNK_SIZE as usize;
 
     let (width, height) = img1.dimensions();
 
-    // Iterate through each chunk in the images.
-    for y in (0..height).step_by(chunk_size) {
+    // The rows of chunks are compared in parallel (rayon), and put back together in order.
+    // Only the pixel comparison runs in parallel: pdfium can't render pages concurrently,
+    // and rendering every page up front would hold the images of the whole document in memory.
+    let rows: Vec<u32> = (0..height).step_by(chunk_size).collect();
+
+    rows.par_iter().flat_map_iter(|&y| {
+        let mut differing_chunks = vec![];
+
+        // Iterate through each chunk in the row.
         for x in (0..width).step_by(chunk_size) {
             
             // Flags to keep track of chunk status relative to ignore rectangles.
@@ -40,13 +378,11 @@ pub fn compare_images_in_chunks(
             // Check if the current chunk overlaps or is inside any of the ignore rectangles.
             if let Some(rects) = ignore_rects {
                 for rect in rects.iter() {
-                    if rect.overlaps(x, y, chunk_size as u32) {
-                        if rect.contains(x, y) && rect.contains(x + chunk_size as u32, y + chunk_size as u32) {
-                            is_chunk_ignored = true;
-                            break;
-                        } else {
-                            is_chunk_partial = true;
-                        }
+                    if rect.covers_chunk(x, y, chunk_size as u32) {
+                        is_chunk_ignored = true;
+                        break;
+                    } else if rect.overlaps(x, y, chunk_size as u32) {
+                        is_chunk_partial = true;
                     }
                 }
             }
@@ -56,8 +392,23 @@ pub fn compare_images_in_chunks(
                 continue;
             }
 
+            // In perceptual mode, the chunk differs if the mean squared error of its pixels is over the threshold
+            if let Some(threshold) = settings.mse_threshold {
+                let partial_ignore_rects = if is_chunk_partial { ignore_rects } else { None };
+
+                if chunk_mean_squared_error(img1, img2, x, y, partial_ignore_rects) > threshold &&
+                    !chunk_matches_shifted(img1, img2, x, y, partial_ignore_rects, settings) {
+                    differing_chunks.push((x, y));
+                }
+
+                continue;
+            }
+
             let mut chunks_differ = false;
 
+            // In center-weighted mode, the weighted sum of the differing pixels in the chunk
+            let mut chunk_score = 0.0;
+
             // Compare each pixel inside the chunk.
             for dy in 0..chunk_size {
                 for dx in 0..chunk_size {
@@ -83,10 +434,22 @@ pub fn compare_images_in_chunks(
                     let img1_pixel = img1.get_pixel(actual_x, actual_y);
                     let img2_pixel = img2.get_pixel(actual_x, actual_y);
 
-                    // If a differing pixel is found, mark the chunk as different and break.
-                    if img1_pixel != img2_pixel {
-                        chunks_differ = true;
-                        break;
+                    // With a neighborhood, the pixel only differs if either image's pixel has no match near it in the other image
+                    // (checking both directions, so a small mark added in only one document is still found).
+                    let pixel_differs = !settings.pixels_match(img1_pixel, img2_pixel) &&
+                        (settings.neighborhood == 0 ||
+                        !has_match_nearby(img2, actual_x, actual_y, img1_pixel, settings) ||
+                        !has_match_nearby(img1, actual_x, actual_y, img2_pixel, settings));
+
+                    if pixel_differs {
+                        if settings.center_weighted_threshold.is_some() {
+                            // Add the pixel's weight to the chunk's score
+                            chunk_score += center_weight(dx as u32, dy as u32, chunk_size as u32);
+                        } else {
+                            // If a differing pixel is found, mark the chunk as different and break.
+                            chunks_differ = true;
+                            break;
+                        }
                     }
                 }
 
@@ -95,126 +458,753 @@ pub fn compare_images_in_chunks(
                 }
             }
 
+            // In center-weighted mode, the chunk only differs if its score is over the threshold
+            if let Some(threshold) = settings.center_weighted_threshold {
+                chunks_differ = chunk_score > threshold;
+            }
+
+            // Content that only shifted by a few pixels is not a difference
+            if chunks_differ {
+                let partial_ignore_rects = if is_chunk_partial { ignore_rects } else { None };
+
+                chunks_differ = !chunk_matches_shifted(img1, img2, x, y, partial_ignore_rects, settings);
+            }
+
             // If the chunk contains differing pixels, add it to the result list.
             if chunks_differ {
                 differing_chunks.push((x, y));
             }
         }
+
+        differing_chunks
+    }).collect()
+}
+
+
+
+// The mean squared error of the color channels of the pixels of a chunk, skipping pixels inside the ignore rectangles.
+// Small changes spread over a chunk (e.g. font hinting) give a low error, where a real change gives a high one.
+fn chunk_mean_squared_error(
+    img1: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    img2: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    x: u32,
+    y: u32,
+    ignore_rects: Option<&IgnoreShapes>,
+) -> f64 {
+    let (width, height) = img1.dimensions();
+
+    let mut sum_of_squares = 0.0;
+    let mut compared_values = 0;
+
+    for actual_y in y..(y + CHUNK_SIZE).min(height) {
+        for actual_x in x..(x + CHUNK_SIZE).min(width) {
+            if let Some(rects) = ignore_rects {
+                if rects.iter().any(|rect| rect.contains(actual_x, actual_y)) {
+                    continue;
+                }
+            }
+
+            let img1_pixel = img1.get_pixel(actual_x, actual_y);
+            let img2_pixel = img2.get_pixel(actual_x, actual_y);
+
+            for channel in 0..3 {
+                let difference = img1_pixel[channel] as f64 - img2_pixel[channel] as f64;
+                sum_of_squares += difference * difference;
+                compared_values += 1;
+            }
+        }
     }
 
-    differing_chunks
+    if compared_values == 0 {
+        0.0
+    } else {
+        sum_of_squares / compared_values as f64
+    }
 }
 
 
+// Check if the chunk of the first image matches the second image shifted by up to +/- shift_tolerance pixels in x and y,
+// skipping pixels inside the ignore rectangles.  Shifts that would reach outside the image are not tried.
+// This costs up to (2 * shift_tolerance + 1)^2 chunk comparisons per differing chunk.
+fn chunk_matches_shifted(
+    img1: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    img2: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    x: u32,
+    y: u32,
+    ignore_rects: Option<&IgnoreShapes>,
+    settings: &ComparisonSettings,
+) -> bool {
+    if settings.shift_tolerance == 0 {
+        return false;
+    }
 
-// Highlight the differing chunks within the image
-pub fn highlight_chunks(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, chunks: &[(u32, u32)]) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let (width, height) = img1.dimensions();
+    let chunk_width = CHUNK_SIZE.min(width - x);
+    let chunk_height = CHUNK_SIZE.min(height - y);
+    let shift = settings.shift_tolerance as i64;
+
+    (-shift..=shift).any(|shift_y| {
+        (-shift..=shift).any(|shift_x| {
+            let (shifted_x, shifted_y) = (x as i64 + shift_x, y as i64 + shift_y);
+
+            if shifted_x < 0 || shifted_y < 0 || shifted_x + chunk_width as i64 > width as i64 || shifted_y + chunk_height as i64 > height as i64 {
+                return false;
+            }
+
+            (0..chunk_height).all(|dy| {
+                (0..chunk_width).all(|dx| {
+                    let (actual_x, actual_y) = (x + dx, y + dy);
+
+                    ignore_rects.is_some_and(|rects| rects.iter().any(|rect| rect.contains(actual_x, actual_y))) ||
+                        settings.pixels_match(img1.get_pixel(actual_x, actual_y), img2.get_pixel(shifted_x as u32 + dx, shifted_y as u32 + dy))
+                })
+            })
+        })
+    })
+}
+
+
+// The weight of a pixel in center-weighted mode: 1.0 at the center of the chunk, falling linearly to 0.0 at the corners.
+// Differences at the edge of a chunk are often anti-aliasing, so they count for less.
+fn center_weight(dx: u32, dy: u32, chunk_size: u32) -> f64 {
+    let center = (chunk_size as f64 - 1.0) / 2.0;
+    let distance = ((dx as f64 - center).powi(2) + (dy as f64 - center).powi(2)).sqrt();
+    let max_distance = (2.0 * center * center).sqrt();
+
+    1.0 - distance / max_distance
+}
+
+
+// Check if the pixel value appears (within the tolerance) within +/- neighborhood pixels of (x, y) in the image.
+// This costs up to (2 * neighborhood + 1)^2 pixel reads per differing pixel.
+fn has_match_nearby(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, y: u32, pixel: &Rgba<u8>, settings: &ComparisonSettings) -> bool {
+    let (width, height) = image.dimensions();
+    let distance = settings.neighborhood;
+
+    let min_x = x.saturating_sub(distance);
+    let max_x = (x + distance).min(width - 1);
+    let min_y = y.saturating_sub(distance);
+    let max_y = (y + distance).min(height - 1);
+
+    (min_y..=max_y).any(|near_y| {
+        (min_x..=max_x).any(|near_x| settings.pixels_match(image.get_pixel(near_x, near_y), pixel))
+    })
+}
+
+
+// The colors used to mark up the page images
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    // Differing chunks: the color of dark pixels, of light pixels, and of all other pixels
+    pub highlight_dark: Rgba<u8>,
+    pub highlight_light: Rgba<u8>,
+    pub highlight_other: Rgba<u8>,
+    // The border of ignored rectangles alternates between these two colors
+    pub ignored_border: [Rgba<u8>; 2],
+    // The base colors of chunks with removed content (only in the first document) and added content (only in the second)
+    pub removed: Rgba<u8>,
+    pub added: Rgba<u8>,
+}
+
+
+// Dark blue, golden and salmon highlights, with red and black borders
+pub const DEFAULT_PALETTE: Palette = Palette {
+    highlight_dark: Rgba([38, 84, 124, 255]),
+    highlight_light: Rgba([255, 209, 102, 255]),
+    highlight_other: Rgba([239, 71, 111, 255]),
+    ignored_border: [Rgba([255, 0, 0, 255]), Rgba([0, 0, 0, 255])],
+    removed: Rgba([220, 30, 30, 255]),
+    added: Rgba([0, 160, 70, 255]),
+};
+
+// Blue and orange colors from the Color Universal Design (Okabe-Ito) palette,
+// which stay distinguishable with red-green color vision deficiency
+pub const CUD_PALETTE: Palette = Palette {
+    highlight_dark: Rgba([0, 114, 178, 255]),
+    highlight_light: Rgba([230, 159, 0, 255]),
+    highlight_other: Rgba([213, 94, 0, 255]),
+    ignored_border: [Rgba([86, 180, 233, 255]), Rgba([0, 0, 0, 255])],
+    removed: Rgba([204, 121, 167, 255]),
+    added: Rgba([0, 158, 115, 255]),
+};
+
+impl Palette {
+
+    // Replace the highlight colors by tints of one base color: darker for dark pixels, lighter for light pixels
+    pub fn with_highlight_color(self, base: Rgba<u8>) -> Palette {
+        let tint = |amount: f64, target: f64| {
+            let mut color = base;
+
+            for channel in 0..3 {
+                color[channel] = (base[channel] as f64 + (target - base[channel] as f64) * amount).round() as u8;
+            }
+
+            color
+        };
+
+        Palette {
+            highlight_dark: tint(0.4, 0.0),
+            highlight_light: tint(0.5, 255.0),
+            highlight_other: base,
+            ..self
+        }
+    }
+}
+
+
+// Parse a highlight color given on the command line: a color name or #RRGGBB
+pub fn parse_highlight_color(value: &str) -> Result<Rgba<u8>, String> {
+    match value.to_lowercase().as_str() {
+        "red" => Ok(Rgba([220, 30, 30, 255])),
+        "blue" => Ok(Rgba([0, 90, 220, 255])),
+        "green" => Ok(Rgba([0, 160, 70, 255])),
+        "orange" => Ok(Rgba([240, 130, 0, 255])),
+        "pink" => Ok(Rgba([239, 71, 111, 255])),
+        "purple" => Ok(Rgba([130, 50, 180, 255])),
+        hex => {
+            let digits = hex.strip_prefix('#')
+                .filter(|digits| digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or_else(|| format!("'{}' is not a color name (red, blue, green, orange, pink, purple) or a #RRGGBB color", value))?;
+
+            let channel = |start: usize| u8::from_str_radix(&digits[start..start + 2], 16).unwrap();
+
+            Ok(Rgba([channel(0), channel(2), channel(4), 255]))
+        }
+    }
+}
+
+
+// Highlight the differing chunks within the image, in place (the image is changed rather than copied)
+pub fn highlight_chunks_mut(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, chunks: &[(u32, u32)], palette: &Palette) {
     let (width, height) = image.dimensions();
-    let mut new_image = image.clone();
 
     for &(x, y) in chunks {
-        for dx in 0..10 {
-            for dy in 0..10 {
+        for dx in 0..CHUNK_SIZE {
+            for dy in 0..CHUNK_SIZE {
                 let new_x = x + dx;
                 let new_y = y + dy;
 
                 // Check if the pixel is in the image
                 if new_x < width && new_y < height {
-                    let pixel = new_image.get_pixel_mut(new_x, new_y);
+                    let pixel = image.get_pixel_mut(new_x, new_y);
 
                     // If the pixel is dark
-                    if pixel[0] < 150 && pixel[1] < 150 && pixel[2] < 150 {
-                        // Change it appropriately (Dark blue)
-                        pixel[0] = 38;
-                        pixel[1] = 84;
-                        pixel[2] = 124;
+                    let color = if pixel[0] < 150 && pixel[1] < 150 && pixel[2] < 150 {
+                        // Change it appropriately (Dark blue by default)
+                        palette.highlight_dark
 
                     // ...else if the pixel is light 
                     } else if pixel[0] > 215 && pixel[1] > 215 && pixel[2] > 215{
-                        // Change it appropriately (Golden)
-                        pixel[0] = 255;
-                        pixel[1] = 209;
-                        pixel[2] = 102; 
-
+                        // Change it appropriately (Golden by default)
+                        palette.highlight_light
 
                     } else {
-                        // Make all other pixels salmonish
-                        pixel[0] = 239;
-                        pixel[1] = 71;
-                        pixel[2] = 111; 
+                        // Make all other pixels salmonish by default
+                        palette.highlight_other
+                    };
 
-                    }
+                    // Keep the pixel's alpha value
+                    pixel[0] = color[0];
+                    pixel[1] = color[1];
+                    pixel[2] = color[2];
                 }
             }
         }
     }
+}
 
-    new_image
+
+// Highlight the differing chunks within the image, in the palette's highlight colors for changed content
+// and in shades of its removed and added colors for content that is only in one of the documents (in place)
+pub fn highlight_classified_chunks_mut(image: &mut RgbaImage, chunks: &[(u32, u32)], changes: &[ChunkChange], palette: &Palette) {
+    let chunks_with_change = |change: ChunkChange| -> Vec<(u32, u32)> {
+        chunks.iter().zip(changes).filter(|(_, chunk_change)| **chunk_change == change).map(|(chunk, _)| *chunk).collect()
+    };
+
+    highlight_chunks_mut(image, &chunks_with_change(ChunkChange::Changed), palette);
+    highlight_chunks_mut(image, &chunks_with_change(ChunkChange::Removed), &palette.with_highlight_color(palette.removed));
+    highlight_chunks_mut(image, &chunks_with_change(ChunkChange::Added), &palette.with_highlight_color(palette.added));
 }
 
 
-// Draw the ignored rectangles on the image
-pub fn draw_ignored_rectangles(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, ignore_rects: Option<&Vec<Rectangle>>) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
-    let mut new_image = image.clone();
-    
-    // Check if rectangles are provided
-    if let Some(rectangles) = ignore_rects {
-        // Iterate over each rectangle
-        for rect in rectangles {
-            
-            let top_left = rect.top_left;
-            let bottom_right = rect.bottom_right;
+// Mark the whole page as heavily changed: a band across the top and a frame around the page, in the highlight color.
+// Used instead of highlighting each difference when there are too many to make sense of (in place).
+pub fn draw_changed_banner_mut(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, palette: &Palette) {
+    let (width, height) = image.dimensions();
 
-            // Draw the top and bottom borders of the rectangle:
-            // Loop from the leftmost to the rightmost x-coordinate of the rectangle.
-            // The x,y values are currently f64, so round them and make them ints
-            for x in (top_left[0].round() as i32)..(bottom_right[0].round() as i32) {
-                // Make sure we're not going out of the image's width boundaries
-                if x >= 0 && x < new_image.width() as i32 {
-                    // Set the top border's pixel color
-                    set_ignored_pixel_border_color(&mut new_image, x, top_left[1].round() as i32);
-                    // Set the bottom border's pixel color
-                    set_ignored_pixel_border_color(&mut new_image, x, bottom_right[1].round() as i32);
-                }
+    // Size the band and frame relative to the page, so they look the same at any resolution
+    let band_height = (height / 40).max(1);
+    let frame_width = (width.min(height) / 200).max(1);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        if y < band_height || x < frame_width || y >= height - frame_width || x >= width - frame_width {
+            *pixel = palette.highlight_other;
+        }
+    }
+}
+
+
+// Create a single image that contains both images side by side, with a gutter of gutter_width pixels (in gutter_color) between them
+pub fn combine_side_by_side(image1: &RgbaImage, image2: &RgbaImage, gutter_width: u32, gutter_color: Rgba<u8>) -> RgbaImage {
+    let total_width = image1.width() + image2.width() + gutter_width;
+    // Images of pages with different sizes are aligned at the top, on a gray background
+    let total_height = image1.height().max(image2.height());
+    let mut combined_image = RgbaImage::from_pixel(total_width, total_height, Rgba([230, 230, 230, 255]));
+
+    // Copy the first image into the new image
+    image::imageops::replace(&mut combined_image, image1, 0, 0);
+
+    // Draw the gutter (black, unless another color was asked for)
+    for y in 0..total_height {
+        for x in image1.width()..image1.width() + gutter_width {
+            combined_image.put_pixel(x, y, gutter_color);
+        }
+    }
+
+    // Copy the second image next to the gutter
+    image::imageops::replace(&mut combined_image, image2, (image1.width() + gutter_width) as i64, 0);
+
+    combined_image
+}
+
+
+// Mark the rows of the differing chunks in the gutter of a combined image (starting at gutter_x),
+// in the color of their change, so differences can be found by scanning down the gutter (in place)
+pub fn mark_differences_in_gutter_mut(image: &mut RgbaImage, gutter_x: u32, gutter_width: u32, chunks: &[(u32, u32)], changes: &[ChunkChange], palette: &Palette) {
+    let height = image.height();
+
+    for (&(_, chunk_y), change) in chunks.iter().zip(changes) {
+        let color = match change {
+            ChunkChange::Removed => palette.removed,
+            ChunkChange::Added => palette.added,
+            ChunkChange::Changed => palette.highlight_other,
+        };
+
+        for y in chunk_y..(chunk_y + CHUNK_SIZE).min(height) {
+            for x in gutter_x..gutter_x + gutter_width {
+                image.put_pixel(x, y, color);
             }
+        }
+    }
+}
+
+
+// Blend two page images into one ("onion skin"): unchanged pixels in grayscale,
+// content only in the first image tinted red, and content only in the second image tinted blue
+pub fn overlay_pages(image1: &RgbaImage, image2: &RgbaImage, settings: &ComparisonSettings) -> RgbaImage {
+    let luma = |pixel: &Rgba<u8>| ((pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000) as u8;
+
+    RgbaImage::from_fn(image1.width(), image1.height(), |x, y| {
+        let pixel1 = image1.get_pixel(x, y);
+        let pixel2 = image2.get_pixel(x, y);
+        let (luma1, luma2) = (luma(pixel1), luma(pixel2));
+
+        if settings.pixels_match(pixel1, pixel2) {
+            Rgba([luma1, luma1, luma1, 255])
+        } else if luma1 <= luma2 {
+            // The first image has the darker pixel, so the content is (mostly) its own
+            Rgba([255, luma1, luma1, 255])
+        } else {
+            Rgba([luma2, luma2, 255, 255])
+        }
+    })
+}
+
+
+// Dim the chunks containing acknowledged differences, so they are visible but clearly not new differences (in place)
+pub fn dim_chunks_mut(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, chunks: &[(u32, u32)]) {
+    let (width, height) = image.dimensions();
+
+    for &(x, y) in chunks {
+        for dx in 0..CHUNK_SIZE {
+            for dy in 0..CHUNK_SIZE {
+                let new_x = x + dx;
+                let new_y = y + dy;
+
+                // Check if the pixel is in the image
+                if new_x < width && new_y < height {
+                    let pixel = image.get_pixel_mut(new_x, new_y);
 
-            // Draw the left and right borders of the rectangle:
-            // Loop from the top to the bottom y-coordinate of the rectangle, excluding the corners.
-            for y in (top_left[1].round() as i32 + 1)..bottom_right[1].round() as i32 {
-                // Make sure we're not going out of the image's height boundaries
-                if y >= 0 && y < new_image.height() as i32 {
-                    // Set the left border's pixel color
-                    set_ignored_pixel_border_color(&mut new_image, top_left[0].round() as i32, y);
-                    // Set the right border's pixel color
-                    set_ignored_pixel_border_color(&mut new_image, bottom_right[0].round() as i32, y);
+                    // Wash each channel halfway towards a light gray
+                    for channel in 0..3 {
+                        pixel[channel] = ((pixel[channel] as u16 + 200) / 2) as u8;
+                    }
                 }
             }
         }
     }
+}
 
-    // Return the modified image
-    new_image
+
+// Write an animated GIF that flips between the two page images, showing each for delay_ms milliseconds
+pub fn save_flip_gif(path: &Path, image1: &RgbaImage, image2: &RgbaImage, delay_ms: u32) -> ImageResult<()> {
+    let file = File::create(path)?;
+
+    // Favor encoding speed over palette quality, as the pages are large
+    let mut encoder = GifEncoder::new_with_speed(file, 30);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    for image in [image1, image2] {
+        let frame = Frame::from_parts(image.clone(), 0, 0, Delay::from_numer_denom_ms(delay_ms, 1));
+        encoder.encode_frame(frame)?;
+    }
+
+    Ok(())
 }
 
 
-// Helper function to set a specific pixel's color
-fn set_ignored_pixel_border_color(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: i32, y: i32) {
+// Draw the outlines of the ignored shapes on the image (in place), as a dashed band of 'thickness' pixels just inside each shape
+pub fn draw_ignored_shapes_mut(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, ignore_shapes: Option<&IgnoreShapes>, palette: &Palette, thickness: u32) {
+    let (width, height) = image.dimensions();
+    
+    // Check if shapes are provided
+    if let Some(shapes) = ignore_shapes {
+        // Iterate over each shape
+        for shape in shapes {
+            // Only the pixels around the shape (and on the image) need to be looked at.
+            // The x,y values are currently f64, so round them and make them ints
+            let [left, top, right, bottom] = shape.bounding_box();
+            let clamp_x = |value: f64| (value.round().max(0.0) as u32).min(width);
+            let clamp_y = |value: f64| (value.round().max(0.0) as u32).min(height);
+
+            for y in clamp_y(top)..clamp_y(bottom + 1.0) {
+                for x in clamp_x(left)..clamp_x(right + 1.0) {
+                    // A pixel is on the outline if it is inside the shape, but the pixel 'thickness' pixels away
+                    // in one of the four directions isn't
+                    let on_outline = shape.contains(x, y) && (
+                        x < thickness || !shape.contains(x - thickness, y) ||
+                        y < thickness || !shape.contains(x, y - thickness) ||
+                        !shape.contains(x + thickness, y) ||
+                        !shape.contains(x, y + thickness)
+                    );
+
+                    if on_outline {
+                        set_ignored_pixel_border_color(image, palette, x as i32, y as i32, thickness as i32);
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+// Helper function to set a specific pixel's color (the dashes are as long as the border is thick)
+fn set_ignored_pixel_border_color(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, palette: &Palette, x: i32, y: i32, thickness: i32) {
     // Check if the given coordinates are within the image boundaries
     if x >= 0 && x < image.width() as i32 && y >= 0 && y < image.height() as i32 {
 
-        // Set every other pixel one color, then the others a different color
-        if (x + y) % 2 == 0 {
-            let pixel = image.get_pixel_mut(x as u32, y as u32);
-            pixel[0] = 255;  // Set the Red channel
-            pixel[1] = 0;    // Set the Green channel
-            pixel[2] = 0;    // Set the Blue channel
+        // Set every other dash one color, then the others a different color
+        let color = palette.ignored_border[(((x + y) / thickness) % 2) as usize];
+
+        let pixel = image.get_pixel_mut(x as u32, y as u32);
+        pixel[0] = color[0];  // Set the Red channel
+        pixel[1] = color[1];  // Set the Green channel
+        pixel[2] = color[2];  // Set the Blue channel
         
-        } else {
-            let pixel = image.get_pixel_mut(x as u32, y as u32);
-            pixel[0] = 0;  // Set the Red channel
-            pixel[1] = 0;    // Set the Green channel
-            pixel[2] = 0;    // Set the Blue channel
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rectangle, RectangleUnit};
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    #[test]
+    fn ignored_shape_outline_is_a_dashed_band_inside_the_shape() {
+        let mut image = RgbaImage::from_pixel(40, 40, WHITE);
+        let shapes: IgnoreShapes = vec![Box::new(Rectangle { page: "all".to_string(), top_left: [10.0, 10.0], bottom_right: [29.0, 29.0], unit: RectangleUnit::Pixel })];
+        let [red, black] = DEFAULT_PALETTE.ignored_border;
+
+        draw_ignored_shapes_mut(&mut image, Some(&shapes), &DEFAULT_PALETTE, 3);
+
+        // The band is 3 pixels wide, just inside each edge; the dashes change color every 3 pixels (along x + y)
+        assert_eq!(*image.get_pixel(10, 10), red);
+        assert_eq!(*image.get_pixel(12, 20), red);
+        assert_eq!(*image.get_pixel(10, 17), black);
+        assert_eq!(*image.get_pixel(27, 20), black);
+        assert_eq!(*image.get_pixel(29, 20), red);
+        assert_eq!(*image.get_pixel(20, 29), red);
+
+        // Inside the band, and outside the shape, nothing is painted
+        assert_eq!(*image.get_pixel(13, 20), WHITE);
+        assert_eq!(*image.get_pixel(20, 20), WHITE);
+        assert_eq!(*image.get_pixel(26, 20), WHITE);
+        assert_eq!(*image.get_pixel(9, 20), WHITE);
+        assert_eq!(*image.get_pixel(30, 20), WHITE);
+
+        // Every painted pixel lies in the band
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let in_band = (10..=29).contains(&x) && (10..=29).contains(&y) && (x <= 12 || x >= 27 || y <= 12 || y >= 27);
+
+            assert_eq!(*pixel != WHITE, in_band, "pixel ({}, {})", x, y);
         }
-        
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn images_a_pixel_or_two_apart_are_padded_with_white() {
+        let black = Rgba([0, 0, 0, 255]);
+        let (image1, image2) = match_image_sizes(RgbaImage::from_pixel(100, 120, black), RgbaImage::from_pixel(102, 119, black), FilterType::Triangle);
+
+        assert_eq!(image1.dimensions(), (102, 120));
+        assert_eq!(image2.dimensions(), (102, 120));
+
+        // The pixels of each page are kept as they are, and the new pixels on the right and at the bottom are white
+        assert_eq!(*image1.get_pixel(99, 119), black);
+        assert_eq!(*image1.get_pixel(100, 0), WHITE);
+        assert_eq!(*image2.get_pixel(101, 118), black);
+        assert_eq!(*image2.get_pixel(0, 119), WHITE);
+    }
+
+    #[test]
+    fn images_further_apart_are_resampled_to_the_larger_size() {
+        let gray = Rgba([128, 128, 128, 255]);
+        let (image1, image2) = match_image_sizes(RgbaImage::from_pixel(50, 60, gray), RgbaImage::from_pixel(100, 120, gray), FilterType::Triangle);
+
+        assert_eq!(image1.dimensions(), (100, 120));
+        assert_eq!(image2.dimensions(), (100, 120));
+
+        // The smaller page is stretched over the whole image, so there is no white padding
+        assert!(image1.pixels().all(|pixel| *pixel == gray));
+
+        // The larger page is the one that is kept
+        let (image1, image2) = match_image_sizes(RgbaImage::from_pixel(100, 120, gray), RgbaImage::from_pixel(97, 120, gray), FilterType::Triangle);
+
+        assert_eq!(image1.dimensions(), (100, 120));
+        assert_eq!(image2.dimensions(), (100, 120));
+        assert!(image2.pixels().all(|pixel| *pixel == gray));
+    }
+
+    #[test]
+    fn images_of_the_same_size_are_left_alone() {
+        let (image1, image2) = match_image_sizes(RgbaImage::from_pixel(30, 40, WHITE), RgbaImage::from_pixel(30, 40, WHITE), FilterType::Nearest);
+
+        assert_eq!(image1.dimensions(), (30, 40));
+        assert_eq!(image2.dimensions(), (30, 40));
+    }
+
+    #[test]
+    fn cud_palette_highlights_in_blue_and_orange() {
+        // A chunk with a dark pixel, a light pixel and a mid-gray pixel, next to a chunk that doesn't differ
+        let mut image = RgbaImage::from_pixel(20, 10, WHITE);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(2, 0, Rgba([180, 180, 180, 255]));
+
+        highlight_chunks_mut(&mut image, &[(0, 0)], &CUD_PALETTE);
+
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 114, 178, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([230, 159, 0, 255]));
+        assert_eq!(*image.get_pixel(2, 0), Rgba([213, 94, 0, 255]));
+        assert_eq!(*image.get_pixel(10, 0), WHITE);
+    }
+
+    #[test]
+    fn cud_palette_borders_ignored_shapes_in_sky_blue_and_black() {
+        let mut image = RgbaImage::from_pixel(40, 40, WHITE);
+        let shapes: IgnoreShapes = vec![Box::new(Rectangle { page: "all".to_string(), top_left: [10.0, 10.0], bottom_right: [29.0, 29.0], unit: RectangleUnit::Pixel })];
+
+        draw_ignored_shapes_mut(&mut image, Some(&shapes), &CUD_PALETTE, 3);
+
+        assert_eq!(*image.get_pixel(10, 10), Rgba([86, 180, 233, 255]));
+        assert_eq!(*image.get_pixel(10, 17), Rgba([0, 0, 0, 255]));
+    }
+
+    // Two 30 x 20 pages: the second has +1 on a few pixels in one chunk, and a black block in another
+    fn pages_with_small_and_large_differences() -> (RgbaImage, RgbaImage) {
+        let gray = Rgba([128, 128, 128, 255]);
+        let image1 = RgbaImage::from_pixel(30, 20, gray);
+        let mut image2 = image1.clone();
+
+        for (x, y) in [(1, 1), (3, 4), (7, 2), (8, 8)] {
+            image2.put_pixel(x, y, Rgba([129, 129, 129, 255]));
+        }
+
+        for y in 10..20 {
+            for x in 20..30 {
+                image2.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+
+        (image1, image2)
+    }
+
+    #[test]
+    fn perceptual_mode_does_not_flag_pixels_off_by_one() {
+        let (image1, image2) = pages_with_small_and_large_differences();
+        let settings = ComparisonSettings { mse_threshold: Some(10.0), ..ComparisonSettings::default() };
+
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &settings), vec![(20, 10)]);
+    }
+
+    #[test]
+    fn exact_mode_flags_pixels_off_by_one() {
+        let (image1, image2) = pages_with_small_and_large_differences();
+
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &ComparisonSettings::default()), vec![(0, 0), (20, 10)]);
+    }
+
+    #[test]
+    fn no_ignored_shapes_leaves_the_image_alone() {
+        let mut image = RgbaImage::from_pixel(20, 20, WHITE);
+
+        draw_ignored_shapes_mut(&mut image, None, &DEFAULT_PALETTE, 3);
+
+        assert!(image.pixels().all(|pixel| *pixel == WHITE));
+    }
+
+    #[test]
+    fn faint_mark_is_only_found_when_amplified() {
+        // A very light gray mark (a faint signature) only on the second page
+        let image1 = RgbaImage::from_pixel(30, 20, WHITE);
+        let mut image2 = image1.clone();
+
+        for x in 12..18 {
+            image2.put_pixel(x, 5, Rgba([250, 250, 250, 255]));
+        }
+
+        // With a tolerance, the mark is too close to white to count as a difference
+        let settings = ComparisonSettings { tolerance: 10, ..ComparisonSettings::default() };
+
+        assert!(compare_images_in_chunks(&image1, &image2, None, &settings).is_empty());
+
+        // Amplified, the mark is 40 shades from white, well over the tolerance
+        assert_eq!(*amplify_faint(&image2).get_pixel(12, 5), Rgba([215, 215, 215, 255]));
+        assert_eq!(compare_images_in_chunks(&amplify_faint(&image1), &amplify_faint(&image2), None, &settings), vec![(10, 0)]);
+    }
+
+    #[test]
+    fn flip_gif_has_a_frame_for_each_page() {
+        use image::AnimationDecoder;
+        use image::codecs::gif::GifDecoder;
+
+        let path = std::env::temp_dir().join(format!("match_pdf-flip-{}.gif", std::process::id()));
+        let image1 = RgbaImage::from_pixel(20, 10, WHITE);
+        let image2 = RgbaImage::from_pixel(20, 10, Rgba([0, 0, 0, 255]));
+
+        save_flip_gif(&path, &image1, &image2, 400).unwrap();
+
+        let frames = GifDecoder::new(File::open(&path).unwrap()).unwrap().into_frames().collect_frames().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The first page, then the second, each shown for the delay
+        assert_eq!(frames.len(), 2);
+        assert_eq!(*frames[0].buffer().get_pixel(5, 5), WHITE);
+        assert_eq!(*frames[1].buffer().get_pixel(5, 5), Rgba([0, 0, 0, 255]));
+        assert_eq!(frames[1].delay().numer_denom_ms(), (400, 1));
+    }
+
+    #[test]
+    fn one_pixel_jitter_matches_within_a_neighborhood_of_one() {
+        // A vertical stroke, one pixel further right on the second page (e.g. different hinting)
+        let stroke_page = |stroke_x: u32| RgbaImage::from_fn(30, 20, |x, y| {
+            if x == stroke_x && (5..15).contains(&y) { Rgba([0, 0, 0, 255]) } else { WHITE }
+        });
+        let (image1, image2) = (stroke_page(12), stroke_page(13));
+
+        let at_neighborhood = |neighborhood: u32| {
+            compare_images_in_chunks(&image1, &image2, None, &ComparisonSettings { neighborhood, ..ComparisonSettings::default() })
+        };
+
+        assert_eq!(at_neighborhood(0), vec![(10, 0), (10, 10)]);
+        assert!(at_neighborhood(1).is_empty());
+    }
+
+    #[test]
+    fn chunks_found_at_a_higher_resolution_land_on_the_standard_chunk_grid() {
+        // At three times the resolution, a chunk of the standard image covers 3 x 3 chunks: the first three chunks
+        // (at 10, 13 and 16 pixels once scaled down) are all in the chunk at 10, and are listed once, on the grid
+        let confirmed_chunks = [(30, 0), (40, 0), (50, 10), (90, 90), (100, 90), (60, 30)];
+
+        assert_eq!(scale_chunks(&confirmed_chunks, (300, 300), (100, 100)), vec![(10, 0), (20, 10), (30, 30)]);
+    }
+
+    #[test]
+    fn only_objects_of_the_chosen_type_are_kept() {
+        use PdfPageObjectType::{Image, Path, Text};
+
+        let object_types = [Text, Image, Path, Text, Image];
+
+        // The objects are removed from the last to the first
+        assert_eq!(objects_to_remove(&object_types, Text), vec![4, 2, 1]);
+        assert_eq!(objects_to_remove(&object_types, Image), vec![3, 2, 0]);
+        assert_eq!(objects_to_remove(&object_types, Path), vec![4, 3, 1, 0]);
+    }
+
+    #[test]
+    fn swapped_image_only_differs_when_comparing_images() {
+        use PdfPageObjectType::{Image, Path, Text};
+
+        // Two pages with the same text and rule, but a different photo
+        let page1 = [(Text, "Invoice"), (Image, "photo A"), (Path, "rule")];
+        let page2 = [(Text, "Invoice"), (Image, "photo B"), (Path, "rule")];
+
+        let kept = |page: &[(PdfPageObjectType, &'static str)], keep: PdfPageObjectType| {
+            let object_types: Vec<PdfPageObjectType> = page.iter().map(|object| object.0).collect();
+            let removed = objects_to_remove(&object_types, keep);
+
+            (0..page.len()).filter(|index| !removed.contains(index)).map(|index| page[index].1).collect::<Vec<_>>()
+        };
+
+        assert_ne!(kept(&page1, Image), kept(&page2, Image));
+        assert_eq!(kept(&page1, Text), kept(&page2, Text));
+    }
+
+    #[test]
+    fn center_weighted_change_counts_at_the_center_of_a_chunk_only() {
+        let settings = ComparisonSettings { center_weighted_threshold: Some(1.0), ..ComparisonSettings::default() };
+        let image1 = RgbaImage::from_pixel(20, 10, WHITE);
+
+        // The same two-pixel change, in the corner of the first chunk and at the center of the second chunk
+        let mut image2 = image1.clone();
+
+        for (x, y) in [(0, 0), (1, 0), (14, 4), (15, 5)] {
+            image2.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+        }
+
+        // The corner pixels weigh almost nothing, the center pixels close to 1 each
+        assert!(center_weight(0, 0, CHUNK_SIZE) + center_weight(1, 0, CHUNK_SIZE) < 1.0);
+        assert!(center_weight(4, 4, CHUNK_SIZE) + center_weight(5, 5, CHUNK_SIZE) > 1.0);
+
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &settings), vec![(10, 0)]);
+
+        // Without center weighting, both changes differ
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &ComparisonSettings::default()), vec![(0, 0), (10, 0)]);
+    }
+
+    #[test]
+    fn changed_banner_frames_the_page() {
+        let mut image = RgbaImage::from_pixel(400, 400, WHITE);
+        draw_changed_banner_mut(&mut image, &DEFAULT_PALETTE);
+
+        // A band across the top (1/40 of the height) and a thin frame around the other sides, with the page left as is inside
+        assert_eq!(*image.get_pixel(200, 9), DEFAULT_PALETTE.highlight_other);
+        assert_eq!(*image.get_pixel(200, 10), WHITE);
+        assert_eq!(*image.get_pixel(0, 200), DEFAULT_PALETTE.highlight_other);
+        assert_eq!(*image.get_pixel(399, 200), DEFAULT_PALETTE.highlight_other);
+        assert_eq!(*image.get_pixel(200, 399), DEFAULT_PALETTE.highlight_other);
+        assert_eq!(*image.get_pixel(200, 200), WHITE);
+    }
+
+    #[test]
+    fn tinted_copy_matches_once_the_background_is_shifted() {
+        // A page with a gray mark, and a copy of it with every color a little darker (as from a scanner or a different renderer)
+        let mut image1 = RgbaImage::from_pixel(30, 20, WHITE);
+        image1.put_pixel(15, 10, Rgba([100, 100, 100, 255]));
+        let tinted = |image: &RgbaImage, darker_by: i16| shift_colors(image, [-darker_by; 3]);
+        let settings = ComparisonSettings::default();
+
+        let image2 = tinted(&image1, 1);
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &settings).len(), 6);
+
+        let shift = background_shift(&image1, &image2).unwrap();
+        assert_eq!(shift, [1, 1, 1]);
+        assert!(compare_images_in_chunks(&image1, &shift_colors(&image2, shift), None, &settings).is_empty());
+
+        // A background more than 8 shades darker is a real difference, so it is not shifted
+        let image2 = tinted(&image1, 9);
+        assert_eq!(background_shift(&image1, &image2), None);
+        assert_eq!(compare_images_in_chunks(&image1, &image2, None, &settings).len(), 6);
+    }
+}
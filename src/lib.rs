@@ -0,0 +1,3685 @@
+// match_pdf: compare two PDF documents page by page, as images
+//
+// The command line (main.rs) is a thin wrapper around this library: other programs can call compare_pdfs
+// with the same options as the flags of the command line.
+
+mod images;
+mod acknowledged;
+mod regions;
+mod baseline;
+mod region_picker;
+mod deskew;
+mod register;
+mod geometry;
+mod form_fields;
+mod render_cache;
+mod text_layer;
+mod batch;
+mod console;
+mod config_check;
+mod shapes;
+mod timing;
+mod stacked_image;
+mod options;
+
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use clap::parser::ValueSource;
+use image::DynamicImage;
+use image::{Rgba, RgbaImage};
+use std::fs::{File};
+use std::io::{Read, Write};
+use std::process;
+use std::path::PathBuf;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use acknowledged::Acknowledgements;
+// The types that make up a comparison result, for programs using the library
+pub use regions::{MovedContent, PointRegion, Region};
+pub use deskew::PageSkew;
+pub use form_fields::FieldDifference;
+pub use text_layer::PageTextDifference;
+pub use options::Options;
+use console::verdict;
+use log::{debug, error, info, log_enabled, warn, Level};
+use shapes::{Circle, IgnoreShape, IgnoreShapes, PageBands, Polygon};
+
+
+// The exit codes: the documents match (0), differences were found (1),
+// the comparison couldn't be done (e.g. a file can't be read or the pdfium library can't be loaded) (2),
+// or a document (or one of its pages) is corrupt and can't be loaded (3)
+pub const DIFFERENCES_EXIT_CODE: i32 = 1;
+pub const ERROR_EXIT_CODE: i32 = 2;
+pub const CORRUPT_PDF_EXIT_CODE: i32 = 3;
+
+
+// An error that stops a comparison, with the exit code the command line ends with for it
+#[derive(Debug)]
+pub struct ComparisonError {
+    pub message: String,
+    pub exit_code: i32,
+}
+
+
+impl ComparisonError {
+
+    // The comparison couldn't be done (ERROR_EXIT_CODE)
+    pub fn new(message: impl Into<String>) -> ComparisonError {
+        ComparisonError { message: message.into(), exit_code: ERROR_EXIT_CODE }
+    }
+
+    // A document, or one of its pages, is corrupt (CORRUPT_PDF_EXIT_CODE)
+    pub fn corrupt(message: impl Into<String>) -> ComparisonError {
+        ComparisonError { message: message.into(), exit_code: CORRUPT_PDF_EXIT_CODE }
+    }
+}
+
+
+impl std::fmt::Display for ComparisonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+
+impl std::error::Error for ComparisonError {}
+
+
+// The exit code for an error returned by a comparison: its own for a ComparisonError, ERROR_EXIT_CODE for any other
+pub fn error_exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    error.downcast_ref::<ComparisonError>().map_or(ERROR_EXIT_CODE, |error| error.exit_code)
+}
+
+
+// Define and collect arguments
+#[derive(Parser, Debug, Clone)]
+#[command(name = "match_pdf")]
+#[command(author = "author")]
+#[command(version = "1.0.7")]
+#[command(about = "MatchPDF compares two pdf documents.", long_about = None)]
+#[command(after_help = "Exit codes: 0 if the documents match, 1 if differences were found, 2 if the comparison could not be done (e.g. a file could not be read or the pdfium library could not be loaded), 3 if a document is corrupt or not a PDF.")]
+pub struct Cli {
+    #[arg(required_unless_present = "validate_config")]
+    pub original_pdf1_path: Option<PathBuf>,
+    #[arg(required_unless_present_any = ["pick_regions", "validate_config"])]
+    pub original_pdf2_path: Option<PathBuf>,
+
+
+    /// An optional 'debug' flag: Include verbose output to the console (log messages at the debug level; the RUST_LOG environment variable overrides the level).
+    #[arg(short, long)]
+    pub debug: bool,
+
+    /// An optional 'stop' flag: Stop the comparison after the first page where differences are found.
+    #[arg(short, long)]
+    pub stop: bool,
+
+    /// An optional 'justdiff' flag: In combination with 'output', only different pages are included in output PDF file.
+    #[arg(short, long)]
+    pub justdiff: bool,
+
+    /// An optional 'pages' flag: Stop the comparison after ## pages if a difference was found in the first ## pages.
+    #[arg(short, long)]
+    pub pages: Option<i32>,
+
+    /// An optional 'maxpages' flag: At a maximum, compare ## pages.
+    #[arg(short, long)]
+    pub maxpages: Option<i32>,
+
+    /// An optional 'from' flag: The first page (base one) to compare.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub from: Option<u16>,
+
+    /// An optional 'to' flag: The last page (base one) to compare.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub to: Option<u16>,
+
+    /// An optional 'page1' flag: Only compare page ## of the first document, with the 'page2' page of the second document (e.g. after pages were inserted).
+    #[arg(long, requires = "page2", conflicts_with_all = ["from", "to", "odd_only", "even_only", "reverse2", "find_page", "baseline_dir", "batch"],
+        value_parser = clap::value_parser!(u16).range(1..))]
+    pub page1: Option<u16>,
+
+    /// An optional 'page2' flag: The page of the second document to compare with the 'page1' page of the first document.
+    #[arg(long, requires = "page1", value_parser = clap::value_parser!(u16).range(1..))]
+    pub page2: Option<u16>,
+
+    /// An optional 'output' flag: Use with a file path to indicate where to place a results PDF file.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// An optional 'result' flag: Use with a file path to indicate where to place a results JSON file.
+    #[arg(short, long)]
+    pub result: Option<PathBuf>,
+
+    /// An optional 'result-jsonl' flag: Use with a file path to append the result of each comparison to, as a line of JSON (JSON Lines), as soon as it is done.
+    #[arg(long)]
+    pub result_jsonl: Option<PathBuf>,
+
+    /// An optional 'config' flag: Use with a file path to indicate where to find the config file.
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// An optional 'acknowledge' flag: Use with a file path to a list of acknowledged differences that should not fail the comparison.
+    #[arg(short, long)]
+    pub acknowledge: Option<PathBuf>,
+
+    /// An optional 'resample' flag: The filter used whenever a page image has to be resized to be compared.
+    #[arg(long, value_enum, default_value_t = ResampleFilter::Triangle)]
+    pub resample: ResampleFilter,
+
+    /// An optional 'split-output' flag: In combination with 'output', write a series of output files with at most ## pages each.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub split_output: Option<u16>,
+
+    /// An optional 'dpi1' flag: Render the first document at ## DPI instead of the standard resolution.
+    #[arg(long, value_parser = geometry::parse_dpi)]
+    pub dpi1: Option<f32>,
+
+    /// An optional 'dpi2' flag: Render the second document at ## DPI instead of the standard resolution.
+    #[arg(long, value_parser = geometry::parse_dpi)]
+    pub dpi2: Option<f32>,
+
+    /// An optional 'create-dirs' flag: Create any missing folders for the output files instead of stopping.
+    #[arg(long)]
+    pub create_dirs: bool,
+
+    /// An optional 'amplify-faint' flag: Compare grayscale versions of the pages with faint (light) marks amplified.
+    #[arg(long)]
+    pub amplify_faint: bool,
+
+    /// An optional 'odd-only' flag: Only compare the odd pages (e.g. the front sides of a duplex document).
+    #[arg(long, conflicts_with = "even_only")]
+    pub odd_only: bool,
+
+    /// An optional 'even-only' flag: Only compare the even pages (e.g. the back sides of a duplex document).
+    #[arg(long)]
+    pub even_only: bool,
+
+    /// An optional 'size-epsilon' flag: Treat page sizes that differ by no more than ## points as equal.
+    #[arg(long, default_value_t = 0.5, value_parser = geometry::parse_size_epsilon)]
+    pub size_epsilon: f32,
+
+    /// An optional 'gif-dir' flag: Use with a folder path to write an animated GIF, flipping between the two documents, for each page with differences.
+    #[arg(long)]
+    pub gif_dir: Option<PathBuf>,
+
+    /// An optional 'gif-delay' flag: In combination with 'gif-dir', the time in milliseconds each document is shown.
+    #[arg(long, default_value_t = 500)]
+    pub gif_delay: u32,
+
+    /// An optional 'detect-moves' flag: Report content that moved on a page as moved, rather than as removed and added.
+    #[arg(long)]
+    pub detect_moves: bool,
+
+    /// An optional 'max-boxes' flag: If a page has more than ## separate areas of differences, mark the whole page as heavily changed instead of highlighting each area.
+    #[arg(long)]
+    pub max_boxes: Option<usize>,
+
+    /// An optional 'reverse2' flag: The second document is in reverse page order (its last page is compared with the first page of the first document).
+    #[arg(long)]
+    pub reverse2: bool,
+
+    /// An optional 'ignore-blank-pages' flag: Leave blank pages out of both documents, so an added (or removed) blank page doesn't count as a difference.
+    #[arg(long, conflicts_with_all = ["page1", "page2", "reverse2", "from", "to", "find_page", "baseline_dir"])]
+    pub ignore_blank_pages: bool,
+
+    /// An optional 'plan' flag: Print which pages would be compared (and skipped), with the rectangles and settings used, then exit without comparing.
+    #[arg(long)]
+    pub plan: bool,
+
+    /// An optional 'strict-config' flag: Fail if the config file has rectangles for pages that are not in the documents.
+    #[arg(long)]
+    pub strict_config: bool,
+
+    /// An optional 'form-fields' flag: Compare the values of the form fields of the documents (by field name) instead of how the pages look.
+    #[arg(long)]
+    pub form_fields: bool,
+
+    /// An optional 'neighborhood' flag: Treat a pixel as unchanged if a matching pixel is within ## pixels in the other document.
+    #[arg(long, default_value_t = 0)]
+    pub neighborhood: u32,
+
+    /// An optional 'confirm-small' flag: Re-compare pages with at most ## differing chunks at a higher resolution, and treat them as matching if the differences disappear.
+    #[arg(long)]
+    pub confirm_small: Option<u32>,
+
+    /// An optional 'confirm-dpi' flag: In combination with 'confirm-small', the resolution (in DPI) used to confirm small differences.
+    #[arg(long, default_value_t = 300.0, value_parser = geometry::parse_dpi)]
+    pub confirm_dpi: f32,
+
+    /// An optional 'coarse-first' flag: Compare each page at a low resolution first, and only render it at full resolution if differences are found there.
+    #[arg(long, conflicts_with_all = ["find_page", "baseline_dir"])]
+    pub coarse_first: bool,
+
+    /// An optional 'object-type' flag: Only render (and compare) the page objects of one type.
+    #[arg(long, value_enum)]
+    pub object_type: Option<ObjectType>,
+
+    /// An optional 'baseline-dir' flag: The second path is a folder of golden page images (page-0001.png, ...) to compare the first document against.
+    #[arg(long)]
+    pub baseline_dir: bool,
+
+    /// An optional 'center-weighted' flag: A chunk only differs if its differing pixels, weighted towards the center of the chunk, add up to more than ##.
+    #[arg(long)]
+    pub center_weighted: Option<f64>,
+
+    /// An optional 'pick-regions' flag: Use with a PNG file path to write a page of the first document with an inch grid, to help author exclusion rectangles.
+    #[arg(long)]
+    pub pick_regions: Option<PathBuf>,
+
+    /// An optional 'validate-config' flag: Check a config file on its own (no documents are needed), report what is wrong with it, then exit.
+    #[arg(long, conflicts_with_all = ["quiet", "batch"])]
+    pub validate_config: Option<PathBuf>,
+
+    /// An optional 'pick-page' flag: In combination with 'pick-regions', the page to use.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub pick_page: u16,
+
+    /// An optional 'pick-region' flag: In combination with 'pick-regions', a rectangle in pixels of the picker image (x1,y1,x2,y2) to convert to a config file rectangle.  Can be repeated.
+    #[arg(long, value_parser = region_picker::parse_pixel_region)]
+    pub pick_region: Vec<[f64; 4]>,
+
+    /// An optional 'password1' flag: The password to open the first document.
+    #[arg(long)]
+    pub password1: Option<String>,
+
+    /// An optional 'password2' flag: The password to open the second document.
+    #[arg(long)]
+    pub password2: Option<String>,
+
+    /// An optional 'password' flag: The password to open both documents, unless 'password1' or 'password2' is given.
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// An optional 'dump-geometry' flag: Print the size of each page in points and pixels, and the scaling used.
+    #[arg(long)]
+    pub dump_geometry: bool,
+
+    /// An optional 'deskew' flag: Detect and correct small rotations of scanned pages before comparing.
+    #[arg(long)]
+    pub deskew: bool,
+
+    /// An optional 'register' flag: Line up the second document's pages with the first (small rotations and shifts, e.g. of scans) before comparing.
+    #[arg(long)]
+    pub register: bool,
+
+    /// An optional 'palette' flag: The colors used to highlight differences and ignored rectangles in the output file.
+    #[arg(long, value_enum, default_value_t = HighlightPalette::Default)]
+    pub palette: HighlightPalette,
+
+    /// An optional 'highlight-color' flag: The base color (red, blue, green, orange, pink, purple or #RRGGBB) to highlight differences with, in place of the palette's highlight colors.
+    #[arg(long, value_parser = images::parse_highlight_color)]
+    pub highlight_color: Option<image::Rgba<u8>>,
+
+    /// An optional 'ignore-top-percent' flag: Ignore a band across the top of every page, ## percent of the page height (e.g. a header with a timestamp).
+    #[arg(long, value_parser = shapes::parse_band_percent)]
+    pub ignore_top_percent: Option<f64>,
+
+    /// An optional 'ignore-bottom-percent' flag: Ignore a band across the bottom of every page, ## percent of the page height (e.g. a footer with page numbers).
+    #[arg(long, value_parser = shapes::parse_band_percent)]
+    pub ignore_bottom_percent: Option<f64>,
+
+    /// An optional 'border-thickness' flag: The thickness (in pixels of the page image) of the dashed border drawn inside ignored rectangles in the output file.
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..=50))]
+    pub border_thickness: u32,
+
+    /// An optional 'find-page' flag: Look for page ## of the first document in the second document, and report the page that matches it best.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub find_page: Option<u16>,
+
+    /// An optional 'render' flag: What is drawn on the page images of both documents: the page content only, plus form fields, plus annotations, or everything.
+    #[arg(long, value_enum, default_value_t = RenderContent::Annotations)]
+    pub render: RenderContent,
+
+    /// An optional 'render-cache' flag: Use with a folder path to keep rendered page images, so unchanged documents are not rendered again in later comparisons.
+    #[arg(long)]
+    pub render_cache: Option<PathBuf>,
+
+    /// An optional 'tolerance' flag: Treat two pixels as equal if each color channel differs by at most ## (0-255).
+    #[arg(long, default_value_t = 0)]
+    pub tolerance: u8,
+
+    /// An optional 'ignore-alpha' flag: Leave the alpha (transparency) channel out when comparing pixels.
+    #[arg(long)]
+    pub ignore_alpha: bool,
+
+    /// An optional 'pdfium-path' flag: Use with a folder path to indicate where to find the pdfium library.
+    #[arg(long)]
+    pub pdfium_path: Option<PathBuf>,
+
+    /// An optional 'perceptual' flag: Compare each chunk by the mean squared error of its pixels, rather than flagging any differing pixel.
+    #[arg(long)]
+    pub perceptual: bool,
+
+    /// An optional 'mse-threshold' flag: In combination with 'perceptual', the mean squared error above which a chunk differs.
+    #[arg(long, default_value_t = 10.0)]
+    pub mse_threshold: f64,
+
+    /// An optional 'render-width' flag: Render pages to fit within ## pixels (default 2000), unless a DPI is given.  Higher values find smaller differences, but are slower.
+    #[arg(long, default_value_t = 2000, value_parser = clap::value_parser!(u16).range(100..))]
+    pub render_width: u16,
+
+    /// An optional 'ignore-rotation' flag: Ignore the rotation set on the pages, so the same content stored with a different rotation matches.
+    #[arg(long)]
+    pub ignore_rotation: bool,
+
+    /// An optional 'image-dir' flag: Use with a folder path to write the highlighted image of each page (side by side) as a PNG file.
+    #[arg(long)]
+    pub image_dir: Option<PathBuf>,
+
+    /// An optional 'single-image' flag: Use with a PNG file path to write the highlighted pages (side by side) stacked in one tall image.
+    #[arg(long, conflicts_with = "batch")]
+    pub single_image: Option<PathBuf>,
+
+    /// An optional 'text' flag: Also compare the text of each page, and report the lines of text that were added or removed.
+    #[arg(long)]
+    pub text: bool,
+
+    /// An optional 'normalize-background' flag: If the background colors of two pages differ slightly (e.g. 254,254,254 instead of white), compare them as if they were the same.
+    #[arg(long)]
+    pub normalize_background: bool,
+
+    /// An optional 'overlay' flag: In the output file, blend the two pages into one (content only in the first document in red, only in the second in blue) instead of showing them side by side.
+    #[arg(long)]
+    pub overlay: bool,
+
+    /// An optional 'gutter-width' (or 'separator-width') flag: The width (in pixels) of the gutter between the two pages in the output file, which marks the rows with differences.
+    #[arg(long, visible_alias = "separator-width", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub gutter_width: u32,
+
+    /// An optional 'separator-color' flag: The color (#RRGGBB or a color name) of the gutter between the two pages in the output file, e.g. for light content on a dark background.
+    #[arg(long, default_value = "#000000", value_parser = images::parse_highlight_color)]
+    pub separator_color: image::Rgba<u8>,
+
+    /// An optional 'output-width-inches' flag: The width of the pages of the output file, in inches (1 to 200).
+    #[arg(long, default_value_t = geometry::DEFAULT_OUTPUT_WIDTH_IN_INCHES, value_parser = geometry::parse_output_width)]
+    pub output_width_inches: f64,
+
+    /// An optional 'shift-tolerance' flag: Treat a differing chunk as unchanged if it matches the other page shifted by up to ## pixels (0 = no shift).
+    #[arg(long, default_value_t = 0)]
+    pub shift_tolerance: u32,
+
+    /// An optional 'batch' flag: The two paths are folders; compare the documents with the same file name in both.  The 'output' path is then a folder, and the 'result' file lists the outcome for each file.
+    #[arg(long, conflicts_with_all = ["baseline_dir", "pick_regions", "find_page"])]
+    pub batch: bool,
+
+    /// An optional 'threads' flag: The number of threads used to compare the chunks of a page (default: one per CPU core).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threads: Option<u32>,
+
+    /// An optional 'quiet' flag: Only print the verdict (MATCH or DIFF) to standard output; errors go to standard error.
+    #[arg(short, long, conflicts_with_all = ["debug", "plan", "pick_regions", "dump_geometry"])]
+    pub quiet: bool,
+
+    // The flags given on the command line, which take precedence over the settings in the config file
+    #[arg(skip)]
+    command_line_flags: Vec<String>,
+
+    /// An optional 'ssim-threshold' flag: Treat a page as matching if its structural similarity (SSIM, up to 1.0 for identical pages) is at least ##, even if some chunks differ.
+    #[arg(long)]
+    pub ssim_threshold: Option<f64>,
+
+    /// An optional 'fail-threshold' flag: Only fail the comparison if more than ## chunks differ in the whole document.  The differences are still in the output and result files.
+    #[arg(long, conflicts_with = "fail_percent")]
+    pub fail_threshold: Option<usize>,
+
+    /// An optional 'fail-percent' flag: Only fail the comparison if more than ## percent of the compared chunks differ in the whole document.  The differences are still in the output and result files.
+    #[arg(long)]
+    pub fail_percent: Option<f64>,
+
+    /// An optional 'expect-diff' flag: Differences are expected: succeed (exit code 0) if they are found, and fail (exit code 1) if the documents match.  The result file still tells whether the documents match.
+    #[arg(long, alias = "invert-match")]
+    pub expect_diff: bool,
+
+}
+
+
+// What is drawn when rendering a page
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum RenderContent {
+    // The page content only
+    Content,
+    // The page content and the values of form fields
+    Forms,
+    // The page content and annotations (but not the values of form fields)
+    Annotations,
+    // Everything
+    All,
+}
+
+impl RenderContent {
+
+    fn includes_form_data(self) -> bool {
+        matches!(self, RenderContent::Forms | RenderContent::All)
+    }
+
+    fn includes_annotations(self) -> bool {
+        matches!(self, RenderContent::Annotations | RenderContent::All)
+    }
+}
+
+
+// The color palettes for marking up the output file
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum HighlightPalette {
+    Default,
+    // Color Universal Design: safe for red-green color vision deficiency
+    Cud,
+}
+
+impl HighlightPalette {
+
+    // Map the command-line choice to the colors used
+    fn palette(self) -> images::Palette {
+        match self {
+            HighlightPalette::Default => images::DEFAULT_PALETTE,
+            HighlightPalette::Cud => images::CUD_PALETTE,
+        }
+    }
+}
+
+
+// The types of page objects that can be compared on their own
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ObjectType {
+    Text,
+    Image,
+    Path,
+}
+
+impl ObjectType {
+
+    // Map the command-line choice to pdfium's page object type
+    fn page_object_type(self) -> PdfPageObjectType {
+        match self {
+            ObjectType::Text => PdfPageObjectType::Text,
+            ObjectType::Image => PdfPageObjectType::Image,
+            ObjectType::Path => PdfPageObjectType::Path,
+        }
+    }
+}
+
+
+// The resampling filters that can be used when a page image is resized
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ResampleFilter {
+    Nearest,
+    Triangle,
+    #[value(name = "catmullrom")]
+    CatmullRom,
+    Lanczos,
+}
+
+impl ResampleFilter {
+
+    // Map the command-line choice to the image crate's filter type
+    fn filter_type(self) -> image::imageops::FilterType {
+        match self {
+            ResampleFilter::Nearest => image::imageops::FilterType::Nearest,
+            ResampleFilter::Triangle => image::imageops::FilterType::Triangle,
+            ResampleFilter::CatmullRom => image::imageops::FilterType::CatmullRom,
+            ResampleFilter::Lanczos => image::imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+
+// Define the structure that will be used for excluded rectangles if a config file is specified
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Rectangle {
+    pub page: String,
+    pub top_left: [f64; 2],
+    pub bottom_right: [f64; 2],
+    // The unit of the coordinates: inches unless specified otherwise
+    #[serde(default, skip_serializing_if = "RectangleUnit::is_inch")]
+    pub unit: RectangleUnit,
+}
+
+
+// The units the coordinates of an excluded rectangle can be given in
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RectangleUnit {
+    #[default]
+    Inch,
+    Point,
+    // Pixels of the rendered page image, used as is
+    Pixel,
+}
+
+impl RectangleUnit {
+
+    fn is_inch(&self) -> bool {
+        *self == RectangleUnit::Inch
+    }
+
+    // The unit's name, as used in messages
+    fn plural(self) -> &'static str {
+        match self {
+            RectangleUnit::Inch => "inches",
+            RectangleUnit::Point => "points",
+            RectangleUnit::Pixel => "pixels",
+        }
+    }
+
+    // Convert an x or y value (or a length) in this unit to pixels of the page image
+    fn to_pixels(self, value: f64, pixels_per_point: f64) -> f64 {
+        match self {
+            // (value in inches) * 72 points per inch * pixels_per_point
+            RectangleUnit::Inch => geometry::inches_to_pixels(value, pixels_per_point).round(),
+            // (value in points) * pixels_per_point
+            RectangleUnit::Point => geometry::points_to_pixels(value, pixels_per_point).round(),
+            // Already in pixels of the page image
+            RectangleUnit::Pixel => value,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Config {
+    #[serde(default)]
+    pub ignored_rectangles: Vec<Rectangle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_circles: Vec<Circle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_polygons: Vec<Polygon>,
+    // Comparison settings for everyone using the config file (flags on the command line take precedence)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Settings>,
+}
+
+
+// The settings a config file can hold, named like the flags they stand for
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Settings {
+    pub tolerance: Option<u8>,
+    pub neighborhood: Option<u32>,
+    pub render_width: Option<u16>,
+    pub highlight_color: Option<String>,
+}
+
+
+impl Default for Cli {
+
+    // The default value of every flag, as if no flag was given on the command line (and without the document paths)
+    fn default() -> Self {
+        Options::default().into_cli()
+    }
+}
+
+
+impl Cli {
+
+    // Use the settings of the config file for the flags that were not given on the command line
+    fn apply_settings(&mut self, settings: &Settings) -> Result<(), String> {
+        let from_config = |flag: &str| !self.command_line_flags.iter().any(|given| given == flag);
+
+        if let (Some(tolerance), true) = (settings.tolerance, from_config("tolerance")) {
+            self.tolerance = tolerance;
+        }
+
+        if let (Some(neighborhood), true) = (settings.neighborhood, from_config("neighborhood")) {
+            self.neighborhood = neighborhood;
+        }
+
+        if let (Some(render_width), true) = (settings.render_width, from_config("render_width")) {
+            if render_width < 100 {
+                return Err(format!("the render width ({}) has to be at least 100 pixels", render_width));
+            }
+
+            self.render_width = render_width;
+        }
+
+        if let (Some(ref highlight_color), true) = (&settings.highlight_color, from_config("highlight_color")) {
+            self.highlight_color = Some(images::parse_highlight_color(highlight_color)?);
+        }
+
+        Ok(())
+    }
+}
+
+
+impl Config {
+
+    // All the shapes of the config file: rectangles, then circles, then polygons
+    fn shapes(&self) -> impl Iterator<Item = &dyn IgnoreShape> {
+        self.ignored_rectangles.iter().map(|rect| rect as &dyn IgnoreShape)
+            .chain(self.ignored_circles.iter().map(|circle| circle as &dyn IgnoreShape))
+            .chain(self.ignored_polygons.iter().map(|polygon| polygon as &dyn IgnoreShape))
+    }
+
+    // This method returns a Vec of the shapes matching a given page value
+
+    // It *also* converts the x,y coordinates from inches to pixels
+    // ...based on the height of the document in points and the height of the rendered image in pixels
+    
+    pub fn get_matching_shapes(&self, page: &str, height_in_points: i32, height_in_pixels: u32) -> IgnoreShapes {
+        // Convert the x,y values from inches (or points) to pixels, based on the height of the PDF page
+
+        // Determine pixels per point
+        // image height in pixels / total height in points
+        let pixels_per_point = geometry::pixels_per_point(height_in_pixels, height_in_points as f64);
+        // println!("Pixels per point calculated as: {}", pixels_per_point.to_string());
+
+        // Convert the x,y values defining the shapes to pixels, using the points value
+        // This conversion will vary based on the size of the PDF page.
+        self.shapes_for_page(page).into_iter()
+            .map(|shape| shape.in_pixels(pixels_per_point))
+            .collect()
+    }
+
+    // Describe the shapes of the given page that are inverted or fall outside a page image of the given size, after conversion to pixels
+    pub fn misplaced_shapes(&self, page: &str, height_in_points: i32, width_in_pixels: u32, height_in_pixels: u32) -> Vec<String> {
+        let pixels_per_point = geometry::pixels_per_point(height_in_pixels, height_in_points as f64);
+
+        self.shapes_for_page(page).into_iter()
+            .filter_map(|shape| {
+                shapes::placement_problem(shape.in_pixels(pixels_per_point).as_ref(), width_in_pixels, height_in_pixels)
+                    .map(|problem| format!("page {}: the {} {} ({} x {} pixels)", page, shape.describe(), problem, width_in_pixels, height_in_pixels))
+            })
+            .collect()
+    }
+
+    // Find the page values of the shapes that can't apply to a document with page_count pages
+    // ("all", "even" and "odd" always apply)
+    pub fn invalid_page_references(&self, page_count: u16) -> Vec<String> {
+        self.shapes()
+            .map(|shape| shape.page())
+            .filter(|page| !matches!(*page, "all" | "even" | "odd"))
+            .filter(|page| !parse_page_list(page).is_some_and(|ranges| ranges.iter().all(|&(first, last)| first >= 1 && last <= page_count)))
+            .map(|page| format!("'{}'", page))
+            .collect()
+    }
+
+    // Find the page values of the shapes that are not "all", "even", "odd", a page number, a range or a list of these
+    pub fn malformed_page_references(&self) -> Vec<String> {
+        self.shapes()
+            .map(|shape| shape.page())
+            .filter(|page| !matches!(*page, "all" | "even" | "odd") && parse_page_list(page).is_none())
+            .map(|page| format!("'{}'", page))
+            .collect()
+    }
+
+    // Find the shapes that apply to the given page, with the x,y values still in inches
+    pub fn shapes_for_page(&self, page: &str) -> Vec<&dyn IgnoreShape> {
+        self.shapes()
+            .filter(|shape| page_matches(shape.page(), page))
+            .collect()
+    }
+}
+
+
+// Check if the page value of a shape applies to the given page
+fn page_matches(page_val: &str, page: &str) -> bool {
+    match page_val {
+        "all" => true,
+        "even" => page.parse::<i32>().is_ok_and(|page_num| page_num % 2 == 0),
+        "odd" => page.parse::<i32>().is_ok_and(|page_num| page_num % 2 == 1),
+        // A page number, a range of pages ("3-7") or a list of these ("1,4,9"); malformed values never match
+        page_val => {
+            match (page.parse::<u16>(), parse_page_list(page_val)) {
+                (Ok(page_num), Some(ranges)) => ranges.iter().any(|&(first, last)| page_num >= first && page_num <= last),
+                _ => false,
+            }
+        }
+    }
+}
+
+
+// Parse the page value of a shape: a page number ("3"), a range of pages ("3-7"), or a comma separated list of these ("1,4,9-12").
+// Returns the inclusive ranges of pages, or None if the value is malformed.
+fn parse_page_list(value: &str) -> Option<Vec<(u16, u16)>> {
+    value.split(',')
+        .map(|part| match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (first.trim().parse::<u16>().ok()?, last.trim().parse::<u16>().ok()?);
+
+                (first <= last).then_some((first, last))
+            },
+            None => part.trim().parse::<u16>().ok().map(|page_num| (page_num, page_num)),
+        })
+        .collect()
+}
+
+
+// The width (and height limit) in pixels at which pages are compared first, with 'coarse-first'
+const COARSE_RENDER_WIDTH: u16 = 500;
+
+
+// Tips for a config file that can't be deserialized
+const CONFIG_FILE_TIPS: &str = "Tips:\
+    \nVerify that the config file contains a valid JSON object.\
+    \nIf excluding more than one region, be sure there is a comma separating their lines in the config file.\
+    \nIf a value between 0 or 1 is desired for x or y, use a zero before the decimal.\
+    \nSee the install folder for an example JSON config file.";
+
+
+// The version of the shape of the result file, to be increased whenever fields are added, changed or removed
+const RESULT_SCHEMA_VERSION: u32 = 5;
+
+
+// The result json output file: what was compared and when, followed by the result
+#[derive(Serialize)]
+struct ResultFile<'a> {
+    schema_version: u32,
+    pdf1_path: String,
+    pdf2_path: Option<String>,
+    // The time of the comparison (UTC, RFC 3339)
+    timestamp: String,
+    #[serde(flatten)]
+    result: &'a ComparisonResult,
+}
+
+
+// The outcome of a comparison: whether differences were found (respecting 'fail-threshold' and 'fail-percent'),
+// and the details that go in the result file
+#[derive(Default)]
+pub struct ComparisonReport {
+    pub differences_found: bool,
+    pub result: ComparisonResult,
+}
+
+
+// Structure for the result of the comparison
+#[derive(Serialize, Deserialize, Default)]
+pub struct ComparisonResult {
+    pub match_result: String,
+    // The page number (base one) of the first page with a difference, null if the documents match
+    pub first_difference_page: Option<i32>,
+    // Content that moved between the documents (only when moves are being detected)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub moved_content: Vec<MovedContent>,
+    // Pages of the document without a golden image, and golden images without a page (only when comparing against a baseline folder)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_baseline_pages: Vec<i32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub orphan_baseline_pages: Vec<i32>,
+    // How many compared pages had how many differing chunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<DifferenceHistogram>,
+    // The skew detected on each page (only when deskewing)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skew_angles: Vec<PageSkew>,
+    // The form fields whose values differ (only when comparing form fields)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub form_field_differences: Vec<FieldDifference>,
+    // The text added or removed on each page (only when comparing text)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub text_differences: Vec<PageTextDifference>,
+    // The differing pages, with consecutive pages collapsed into ranges (e.g. "5-7, 9")
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub differing_pages: String,
+    // The details of each compared page
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<PageResult>,
+    // The differing chunks of all compared pages by how their content changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_changes: Option<ChunkChangeCounts>,
+    // The average difference percentage of the compared pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difference_percentage: Option<f64>,
+    // The average structural similarity of the compared pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssim: Option<f64>,
+    // The config file shapes that are inverted or fall outside the page they apply to, with the page number
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shape_warnings: Vec<String>,
+    // The page of the second document that best matches the page being looked for (only when finding a page)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found_page: Option<FoundPage>,
+}
+
+
+// The result of comparing a page
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PageResult {
+    pub page: i32,
+    pub differs: bool,
+    pub differing_chunks: usize,
+    // The box (in pixels) around all the differing chunks, null if there are none
+    pub bounding_box: Option<Region>,
+    // The same box in points of the PDF page, from its bottom left corner (as PDF annotations are placed), null if there are none
+    pub bounding_box_points: Option<PointRegion>,
+    // The percentage of the compared (not ignored) chunks that differ
+    pub difference_percentage: f64,
+    // Set when ignore rectangles cover the whole page, so nothing was compared
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub all_chunks_ignored: bool,
+    // The differing chunks by how their content changed
+    pub chunk_changes: ChunkChangeCounts,
+    // The structural similarity of the pages (1.0 for identical pages), null if nothing was compared
+    pub ssim: Option<f64>,
+}
+
+
+// The number of differing chunks with content only in the first document (removed), only in the second (added), or in both (changed)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub struct ChunkChangeCounts {
+    pub removed: usize,
+    pub added: usize,
+    pub changed: usize,
+}
+
+impl ChunkChangeCounts {
+
+    fn new(changes: &[images::ChunkChange]) -> ChunkChangeCounts {
+        let count = |change: images::ChunkChange| changes.iter().filter(|chunk_change| **chunk_change == change).count();
+
+        ChunkChangeCounts {
+            removed: count(images::ChunkChange::Removed),
+            added: count(images::ChunkChange::Added),
+            changed: count(images::ChunkChange::Changed),
+        }
+    }
+
+    fn add(&mut self, other: &ChunkChangeCounts) {
+        self.removed += other.removed;
+        self.added += other.added;
+        self.changed += other.changed;
+    }
+}
+
+
+// The page of the second document that best matches a page of the first document
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FoundPage {
+    pub page: i32,
+    // The fraction of the chunks of the page that differ (0.0 is an exact match)
+    pub difference_ratio: f64,
+}
+
+
+// Distribution of the number of differing chunks per compared page
+#[derive(Serialize, Deserialize, Default)]
+pub struct DifferenceHistogram {
+    #[serde(rename = "0")]
+    pub no_differences: u32,
+    #[serde(rename = "1-10")]
+    pub up_to_10: u32,
+    #[serde(rename = "11-100")]
+    pub up_to_100: u32,
+    #[serde(rename = "101+")]
+    pub over_100: u32,
+}
+
+impl DifferenceHistogram {
+
+    // Count a page with the given number of differing chunks
+    fn add_page(&mut self, differing_chunks: usize) {
+        match differing_chunks {
+            0 => self.no_differences += 1,
+            1..=10 => self.up_to_10 += 1,
+            11..=100 => self.up_to_100 += 1,
+            _ => self.over_100 += 1,
+        }
+    }
+}
+
+
+
+// Parse the command line arguments, keeping track of the flags that were given
+pub fn parse_command_line() -> Cli {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    cli.command_line_flags = matches.ids()
+        .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+        .map(|id| id.to_string())
+        .collect();
+
+    cli
+}
+
+
+// Run what the command line asks for (a comparison of two documents, or of two folders in batch mode),
+// returning whether it failed: differences were found or, if differences are expected, the documents match
+pub fn run(cli: Cli) -> Result<bool, Box<dyn std::error::Error>> {
+    console::init_logger(cli.debug, cli.quiet);
+
+    // Limit the threads the comparison uses (e.g. on a shared build server); rayon uses one per CPU core otherwise
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads as usize).build_global()?;
+    }
+
+    // Checking a config file doesn't compare anything: an invalid config file is an error
+    if let Some(ref path) = cli.validate_config {
+        if !config_check::validate_config_file(path) {
+            return Err(ComparisonError::new(format!("The config file {} is not valid.", path.display())).into());
+        }
+
+        return Ok(false);
+    }
+
+    let expect_diff = cli.expect_diff;
+
+    let differences_found = if cli.batch {
+        batch::compare_folders(&cli)?
+    } else {
+        compare(cli)?.differences_found
+    };
+
+    if expect_diff {
+        if differences_found {
+            verdict!("Differences were expected, and found.");
+        } else {
+            verdict!("Differences were expected, but none were found.");
+        }
+    }
+
+    Ok(differences_found != expect_diff)
+}
+
+
+// Compare two PDF documents, for use from other programs.
+// Like on the command line, output and result files are only written if their paths are set.
+// Invalid options or documents are returned as a ComparisonError.  Messages go to the logger of the calling program (if it has one).
+pub fn compare_pdfs(path1: &Path, path2: &Path, options: Options) -> Result<ComparisonReport, Box<dyn std::error::Error>> {
+    // The command line checks its flags with clap; options set in code are checked here
+    if let Some(message) = options.option_error() {
+        return Err(ComparisonError::new(message).into());
+    }
+
+    let mut cli = options.into_cli();
+    cli.original_pdf1_path = Some(path1.to_path_buf());
+    cli.original_pdf2_path = Some(path2.to_path_buf());
+
+    compare(cli)
+}
+
+
+// Run the comparison the command line asks for, returning the verdict and the details of the result
+fn compare(mut cli: Cli) -> Result<ComparisonReport, Box<dyn std::error::Error>> {
+
+    // Define global variables
+    let mut differences_found_in_document: bool = false;
+    let mut differences_found_in_page: bool = false;
+    let mut different_pages_count: i32 = 0;
+    let mut differences_in_number_of_pages: bool = false;
+    let mut config_json: Option<Config> = None;
+    let mut acknowledgements: Option<Acknowledgements> = None;
+    let mut moved_content: Vec<MovedContent> = Vec::new();
+    let mut differing_pages: Vec<i32> = Vec::new();
+    let mut page_results: Vec<PageResult> = Vec::new();
+    let mut histogram = DifferenceHistogram::default();
+    let mut compared_chunks_in_document: usize = 0;
+    let mut skew_angles: Vec<PageSkew> = Vec::new();
+    let mut shape_warnings: Vec<String> = Vec::new();
+    let mut text_differences: Vec<PageTextDifference> = Vec::new();
+    let mut document_chunk_changes = ChunkChangeCounts::default();
+    let mut page_timings: Vec<timing::PageTimings> = Vec::new();
+    let mut saving_time = Duration::ZERO;
+
+
+    
+    // If debug messages are logged (the debug flag, or RUST_LOG), print some flag and argument messages to the console
+    if log_enabled!(Level::Debug) {
+        debug!("The 'debug' flag was set.  More information will be provided at the console.");
+    
+        if let Some(ref path) = cli.original_pdf1_path {
+            debug!("pdf1: {}", path.display());
+        }
+        if let Some(ref path) = cli.original_pdf2_path {
+            debug!("pdf2: {}", path.display());
+        }
+    
+        if cli.stop {
+            debug!("The 'stop' flag was set.  The comparison will stop after the first page with differences.");
+        } else {
+            debug!("The 'stop' flag was not set.");
+        }
+    
+        if cli.justdiff {
+            debug!("The 'justdiff' flag was set.  Only different pages will be included in the output file.");
+        } else {
+            debug!("The 'justdiff' flag was not set.");
+        }
+
+        match cli.pages {
+            Some(value) => debug!("The 'pages' flag was set with value:  {}", value),
+            None => debug!("The 'pages' flag was not set."),
+        }
+    
+        match cli.maxpages {
+            Some(value) => debug!("The 'maxpages' flag was set with value:  {}", value),
+            None => debug!("The 'maxpages' flag was not set."),
+        }
+    
+        match cli.from {
+            Some(value) => debug!("The 'from' flag was set with value:  {}", value),
+            None => debug!("The 'from' flag was not set."),
+        }
+
+        match cli.to {
+            Some(value) => debug!("The 'to' flag was set with value:  {}", value),
+            None => debug!("The 'to' flag was not set."),
+        }
+
+        match (cli.page1, cli.page2) {
+            (Some(page1), Some(page2)) => debug!("The 'page1' and 'page2' flags were set with values:  {} and {}", page1, page2),
+            _ => debug!("The 'page1' and 'page2' flags were not set."),
+        }
+
+        match cli.output {
+            Some(ref value) => debug!("The 'output' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'output' flag was not set."),
+        }
+    
+        match cli.result {
+            Some(ref value) => debug!("The 'result' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'result' flag was not set."),
+        }
+
+        match cli.result_jsonl {
+            Some(ref value) => debug!("The 'result-jsonl' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'result-jsonl' flag was not set."),
+        }
+
+        match cli.config {
+            Some(ref value) => debug!("The 'config' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'config' flag was not set."),
+        }
+
+        match cli.acknowledge {
+            Some(ref value) => debug!("The 'acknowledge' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'acknowledge' flag was not set."),
+        }
+
+        debug!("The 'resample' flag value is:  {:?}", cli.resample);
+
+        debug!("The 'palette' flag value is:  {:?}", cli.palette);
+
+        match cli.highlight_color {
+            Some(color) => debug!("The 'highlight-color' flag was set with value:  #{:02X}{:02X}{:02X}", color[0], color[1], color[2]),
+            None => debug!("The 'highlight-color' flag was not set."),
+        }
+
+        debug!("The 'border-thickness' flag value is:  {}", cli.border_thickness);
+
+        match cli.ignore_top_percent {
+            Some(value) => debug!("The 'ignore-top-percent' flag was set with value:  {}", value),
+            None => debug!("The 'ignore-top-percent' flag was not set."),
+        }
+
+        match cli.ignore_bottom_percent {
+            Some(value) => debug!("The 'ignore-bottom-percent' flag was set with value:  {}", value),
+            None => debug!("The 'ignore-bottom-percent' flag was not set."),
+        }
+
+        debug!("The 'render' flag value is:  {:?}", cli.render);
+
+        debug!("The 'render-width' flag value is:  {}", cli.render_width);
+
+        match cli.image_dir {
+            Some(ref value) => debug!("The 'image-dir' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'image-dir' flag was not set."),
+        }
+
+        match cli.single_image {
+            Some(ref value) => debug!("The 'single-image' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'single-image' flag was not set."),
+        }
+
+        if cli.text {
+            debug!("The 'text' flag was set.  The text of the pages will be compared as well.");
+        } else {
+            debug!("The 'text' flag was not set.");
+        }
+
+        match cli.ssim_threshold {
+            Some(value) => debug!("The 'ssim-threshold' flag was set with value:  {}", value),
+            None => debug!("The 'ssim-threshold' flag was not set."),
+        }
+
+        match cli.fail_threshold {
+            Some(value) => debug!("The 'fail-threshold' flag was set with value:  {}", value),
+            None => debug!("The 'fail-threshold' flag was not set."),
+        }
+
+        match cli.fail_percent {
+            Some(value) => debug!("The 'fail-percent' flag was set with value:  {}", value),
+            None => debug!("The 'fail-percent' flag was not set."),
+        }
+
+        if cli.expect_diff {
+            debug!("The 'expect-diff' flag was set.  Finding differences is a success.");
+        } else {
+            debug!("The 'expect-diff' flag was not set.");
+        }
+
+        if cli.normalize_background {
+            debug!("The 'normalize-background' flag was set.  Slightly different background colors will be treated as the same.");
+        } else {
+            debug!("The 'normalize-background' flag was not set.");
+        }
+
+        if cli.overlay {
+            debug!("The 'overlay' flag was set.  The pages will be blended into one in the output file.");
+        } else {
+            debug!("The 'overlay' flag was not set.");
+        }
+
+        debug!("The 'gutter-width' flag value is:  {}", cli.gutter_width);
+        debug!("The 'separator-color' flag value is:  {:?}", cli.separator_color.0);
+
+        debug!("The 'output-width-inches' flag value is:  {}", cli.output_width_inches);
+
+        debug!("The 'shift-tolerance' flag value is:  {}", cli.shift_tolerance);
+
+        if cli.ignore_rotation {
+            debug!("The 'ignore-rotation' flag was set.  Pages will be compared without their rotation.");
+        } else {
+            debug!("The 'ignore-rotation' flag was not set.");
+        }
+
+        debug!("The 'tolerance' flag value is:  {}", cli.tolerance);
+
+        if cli.ignore_alpha {
+            debug!("The 'ignore-alpha' flag was set.  The alpha channel will not be compared.");
+        } else {
+            debug!("The 'ignore-alpha' flag was not set.");
+        }
+
+        if cli.perceptual {
+            debug!("The 'perceptual' flag was set.  Chunks with a mean squared error over {} will be flagged.", cli.mse_threshold);
+        } else {
+            debug!("The 'perceptual' flag was not set.");
+        }
+
+        match cli.pdfium_path {
+            Some(ref value) => debug!("The 'pdfium-path' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'pdfium-path' flag was not set."),
+        }
+
+        match cli.render_cache {
+            Some(ref value) => debug!("The 'render-cache' flag was set with value:  {}", value.to_string_lossy()),
+            None => debug!("The 'render-cache' flag was not set."),
+        }
+
+        match cli.find_page {
+            Some(value) => debug!("The 'find-page' flag was set with value:  {}", value),
+            None => debug!("The 'find-page' flag was not set."),
+        }
+
+        match cli.split_output {
+            Some(value) => debug!("The 'split-output' flag was set with value:  {}", value),
+            None => debug!("The 'split-output' flag was not set."),
+        }
+
+        match cli.dpi1 {
+            Some(value) => debug!("The 'dpi1' flag was set with value:  {}", value),
+            None => debug!("The 'dpi1' flag was not set."),
+        }
+
+        match cli.dpi2 {
+            Some(value) => debug!("The 'dpi2' flag was set with value:  {}", value),
+            None => debug!("The 'dpi2' flag was not set."),
+        }
+
+        if cli.create_dirs {
+            debug!("The 'create-dirs' flag was set.  Missing output folders will be created.");
+        } else {
+            debug!("The 'create-dirs' flag was not set.");
+        }
+
+        if cli.amplify_faint {
+            debug!("The 'amplify-faint' flag was set.  Faint marks will be amplified before comparing.");
+        } else {
+            debug!("The 'amplify-faint' flag was not set.");
+        }
+
+        if cli.odd_only {
+            debug!("The 'odd-only' flag was set.  Only odd pages will be compared.");
+        } else if cli.even_only {
+            debug!("The 'even-only' flag was set.  Only even pages will be compared.");
+        }
+
+        debug!("The 'size-epsilon' flag value is:  {}", cli.size_epsilon);
+
+        match cli.gif_dir {
+            Some(ref value) => debug!("The 'gif-dir' flag was set with value:  {} (delay {} ms)", value.to_string_lossy(), cli.gif_delay),
+            None => debug!("The 'gif-dir' flag was not set."),
+        }
+
+        if cli.detect_moves {
+            debug!("The 'detect-moves' flag was set.  Moved content will be reported as moved.");
+        } else {
+            debug!("The 'detect-moves' flag was not set.");
+        }
+
+        match cli.max_boxes {
+            Some(value) => debug!("The 'max-boxes' flag was set with value:  {}", value),
+            None => debug!("The 'max-boxes' flag was not set."),
+        }
+
+        if cli.reverse2 {
+            debug!("The 'reverse2' flag was set.  The pages of the second document will be compared in reverse order.");
+        } else {
+            debug!("The 'reverse2' flag was not set.");
+        }
+
+        if cli.ignore_blank_pages {
+            debug!("The 'ignore-blank-pages' flag was set.  Blank pages will be left out of both documents.");
+        } else {
+            debug!("The 'ignore-blank-pages' flag was not set.");
+        }
+
+        if cli.plan {
+            debug!("The 'plan' flag was set.  The comparison plan will be printed, and no comparison will be made.");
+        } else {
+            debug!("The 'plan' flag was not set.");
+        }
+
+        if cli.strict_config {
+            debug!("The 'strict-config' flag was set.  Config rectangles for pages that don't exist will be an error.");
+        } else {
+            debug!("The 'strict-config' flag was not set.");
+        }
+
+        if cli.form_fields {
+            debug!("The 'form-fields' flag was set.  The values of the form fields will be compared instead of the pages.");
+        } else {
+            debug!("The 'form-fields' flag was not set.");
+        }
+
+        debug!("The 'neighborhood' flag value is:  {}", cli.neighborhood);
+
+        match cli.confirm_small {
+            Some(value) => debug!("The 'confirm-small' flag was set with value:  {} (confirming at {} DPI)", value, cli.confirm_dpi),
+            None => debug!("The 'confirm-small' flag was not set."),
+        }
+
+        match cli.threads {
+            Some(value) => debug!("The 'threads' flag was set with value:  {}", value),
+            None => debug!("The 'threads' flag was not set.  The comparison uses one thread per CPU core."),
+        }
+
+        if cli.coarse_first {
+            debug!("The 'coarse-first' flag was set.  Pages will be compared at {} pixels first, and only rendered at full resolution if they differ.", COARSE_RENDER_WIDTH);
+        } else {
+            debug!("The 'coarse-first' flag was not set.");
+        }
+
+        match cli.object_type {
+            Some(value) => debug!("The 'object-type' flag was set with value:  {:?}", value),
+            None => debug!("The 'object-type' flag was not set."),
+        }
+
+        if cli.baseline_dir {
+            debug!("The 'baseline-dir' flag was set.  The first document will be compared against the golden images in the second path.");
+        } else {
+            debug!("The 'baseline-dir' flag was not set.");
+        }
+
+        match cli.center_weighted {
+            Some(value) => debug!("The 'center-weighted' flag was set with value:  {}", value),
+            None => debug!("The 'center-weighted' flag was not set."),
+        }
+
+        match cli.pick_regions {
+            Some(ref value) => debug!("The 'pick-regions' flag was set with value:  {} (page {})", value.to_string_lossy(), cli.pick_page),
+            None => debug!("The 'pick-regions' flag was not set."),
+        }
+
+        // Only say whether a password was given, never print the password itself
+        debug!("A password for the first document was {}.", if cli.password1.is_some() { "given" } else { "not given" });
+        debug!("A password for the second document was {}.", if cli.password2.is_some() { "given" } else { "not given" });
+        debug!("A password for both documents was {}.", if cli.password.is_some() { "given" } else { "not given" });
+
+        if cli.dump_geometry {
+            debug!("The 'dump-geometry' flag was set.  The geometry of each page will be printed.");
+        } else {
+            debug!("The 'dump-geometry' flag was not set.");
+        }
+
+        if cli.deskew {
+            debug!("The 'deskew' flag was set.  Skewed (slightly rotated) pages will be leveled before comparing.");
+        } else {
+            debug!("The 'deskew' flag was not set.");
+        }
+
+        if cli.register {
+            debug!("The 'register' flag was set.  The pages of the second document will be lined up with the first before comparing.");
+        } else {
+            debug!("The 'register' flag was not set.");
+        }
+    
+    } 
+
+
+
+
+    // ****************************************************************************** //
+    // ******************** Command-Line Info Verification ************************** //
+    // ****************************************************************************** //
+
+
+    // PDF Output File
+
+    // If the user provided an output file, check to see if the included folder exists
+    if let Some(ref path) = cli.output {
+        check_parent_folder(path, "output", cli.create_dirs)?;
+    }
+
+    // Single Image File
+
+    // If the user provided a single image file, check to see if the included folder exists
+    if let Some(ref path) = cli.single_image {
+        check_parent_folder(path, "single-image", cli.create_dirs)?;
+    }
+
+    // Results File (JSON)
+
+    // If the user provided a result file, check to see if the included folder exists
+    if let Some(ref path) = cli.result {
+        check_parent_folder(path, "result", cli.create_dirs)?;
+    }
+
+    if let Some(ref path) = cli.result_jsonl {
+        check_parent_folder(path, "result-jsonl", cli.create_dirs)?;
+    }
+
+
+    // GIF Output Folder
+
+    // If the user provided a GIF folder, check to see if it exists
+    if let Some(ref path) = cli.gif_dir {
+        check_folder(path, "GIF", cli.create_dirs)?;
+    }
+
+
+    // Image Output Folder
+
+    // If the user provided an image folder, check to see if it exists
+    if let Some(ref path) = cli.image_dir {
+        check_folder(path, "image", cli.create_dirs)?;
+    }
+
+
+    // Standard Input
+
+    // Only one document can be read from standard input ('-')
+    let pdf1_from_stdin = cli.original_pdf1_path.as_deref().is_some_and(is_stdin);
+    let pdf2_from_stdin = cli.original_pdf2_path.as_deref().is_some_and(is_stdin);
+
+    if pdf1_from_stdin && pdf2_from_stdin {
+        return Err(ComparisonError::new("Only one of the documents can be read from standard input ('-').").into());
+    }
+
+
+    // Render Cache Folder
+
+    // If the user provided a render cache folder, check to see if it exists
+    if let Some(ref path) = cli.render_cache {
+        check_folder(path, "render cache", cli.create_dirs)?;
+
+        // Cached pages are found by the contents of the document files, which can't be read twice from standard input
+        if pdf1_from_stdin || pdf2_from_stdin {
+            return Err(ComparisonError::new("The 'render-cache' flag can't be used with a document read from standard input ('-').").into());
+        }
+    }
+
+
+    // Config file (JSON for Exclusion Zones)
+
+    // If the config argument was used, evaluate and prep the data
+    if let Some(ref _value) = cli.config {
+
+        if let Some(ref path) = cli.config {
+            
+            // If the config path does not exist, exit now.
+            if ! Path::new(path).exists() {
+                return Err(ComparisonError::new("The specified config file does not exist.").into());
+            } 
+
+            // Consume the contents of the json file, placing them into the previously defined JSON object
+            // Read the file to a string
+            let mut content = String::new();
+
+            if let Err(e) = File::open(path).and_then(|mut file| file.read_to_string(&mut content)) {
+                return Err(ComparisonError::new(format!("Failed to read the specified config file: {}", e)).into());
+            }
+
+            // Deserialize the JSON content to the Config struct (the error gives the line and column of the problem)
+            match serde_json::from_str(&content) {
+                Ok(config) => config_json = Some(config),
+                Err(e) => {
+                    return Err(ComparisonError::new(format!("Failed to deserialize JSON in config file: {}\n{}", e, CONFIG_FILE_TIPS)).into());
+                }
+            }
+            
+            // println!("{:?}", config_json);
+
+            // Rectangles with a page value that can't be understood are never used, so point them out
+            if let Some(ref config) = config_json {
+                let malformed_pages = config.malformed_page_references();
+
+                if !malformed_pages.is_empty() {
+                    warn!("Warning: the config file has rectangles with page values that are not understood, so they are skipped: {}", malformed_pages.join(", "));
+                }
+            }
+
+            // Settings in the config file fill in for the flags that were not given
+            if let Some(settings) = config_json.as_ref().and_then(|config| config.settings.as_ref()) {
+                if let Err(message) = cli.apply_settings(settings) {
+                    return Err(ComparisonError::new(format!("The settings in the config file are not valid: {}.", message)).into());
+                }
+
+                debug!("Settings from the config file: {:?}", settings);
+            }
+
+        } 
+    }
+
+
+    // Acknowledged differences file (JSON of accepted differences)
+
+    // If the acknowledge argument was used, load the acknowledged regions
+    if let Some(ref path) = cli.acknowledge {
+
+        // If the acknowledge path does not exist, exit now.
+        if ! Path::new(path).exists() {
+            return Err(ComparisonError::new("The specified acknowledge file does not exist.").into());
+        }
+
+        match Acknowledgements::load(path) {
+            Ok(loaded) => acknowledgements = Some(loaded),
+            Err(message) => {
+                return Err(ComparisonError::new(message).into());
+            }
+        }
+    }
+
+
+    // Identical Files
+
+    // Two files with the same bytes match, so there is nothing to render.  This is skipped when pages of the same document
+    // can be compared with different pages (or written to the output), where the result depends on more than the bytes,
+    // and when the pages are asked to be looked at anyway ('dump-geometry', 'deskew' and 'text' report on every page).
+    let page_images_wanted = !cli.justdiff && (cli.output.is_some() || cli.image_dir.is_some() || cli.single_image.is_some());
+    let fast_path_possible = !page_images_wanted && !cli.plan && !cli.reverse2 && cli.page1.is_none() &&
+        cli.find_page.is_none() && !cli.baseline_dir && cli.pick_regions.is_none() && !pdf1_from_stdin && !pdf2_from_stdin &&
+        !cli.dump_geometry && !cli.deskew && !cli.text;
+
+    if let (true, Some(path1), Some(path2)) = (fast_path_possible, cli.original_pdf1_path.as_deref(), cli.original_pdf2_path.as_deref()) {
+        if files_are_identical(path1, path2)? {
+            // The page references are checked as they would be without the fast path, which needs the number of pages
+            // (the first document is loaded to count them, but nothing is rendered)
+            if cli.from.is_some() || cli.to.is_some() || (cli.strict_config && config_json.is_some()) {
+                let pdfium = Pdfium::new(bind_pdfium(cli.pdfium_path.as_deref())?);
+                let pages = load_document(&pdfium, path1, document_passwords(&cli)[0], "first", "--password1 or --password")?.pages().len();
+
+                if let Some(message) = page_reference_error(&cli, config_json.as_ref(), pages, pages) {
+                    return Err(ComparisonError::new(message).into());
+                }
+            }
+
+            debug!("The two files are byte for byte identical, so the documents match without being rendered (fast path).");
+
+            verdict!("The PDF documents match.");
+
+            let result = ComparisonResult {
+                match_result: "Documents match".to_string(),
+                ..Default::default()
+            };
+
+            write_results(&cli, &result)?;
+
+            return Ok(ComparisonReport { differences_found: false, result });
+        }
+    }
+
+
+    // Bind to the pdfium library (external, pre-built pdfium.dll)
+
+    let pdfium = Pdfium::new(bind_pdfium(cli.pdfium_path.as_deref())?);
+
+    // Set how strictly the page images are compared
+    let comparison_settings = images::ComparisonSettings {
+        neighborhood: cli.neighborhood,
+        amplify_faint: cli.amplify_faint,
+        center_weighted_threshold: cli.center_weighted,
+        tolerance: cli.tolerance,
+        ignore_alpha: cli.ignore_alpha,
+        mse_threshold: if cli.perceptual { Some(cli.mse_threshold) } else { None },
+        normalize_background: cli.normalize_background,
+        shift_tolerance: cli.shift_tolerance,
+        ignored_bands: PageBands {
+            top_percent: cli.ignore_top_percent,
+            bottom_percent: cli.ignore_bottom_percent,
+        },
+    };
+
+    // Load the pdf documents...
+    let original_pdf1_path = cli.original_pdf1_path.clone().expect("The first path is required.");
+    let pdf_document_1 = load_document(&pdfium, &original_pdf1_path, document_passwords(&cli)[0], "first", "--password1 or --password")?;
+
+
+    // If regions are being picked, help the user author exclusion rectangles instead of comparing
+    if let Some(ref picker_path) = cli.pick_regions {
+
+        let page_index = cli.pick_page - 1;
+
+        if page_index >= pdf_document_1.pages().len() {
+            return Err(ComparisonError::new(format!("The document does not have a page {}.", cli.pick_page)).into());
+        }
+
+        let page = pdf_document_1.pages().get(page_index)?;
+        let page_image = images::render_page(&page, &build_render_config(cli.dpi1, &cli))?;
+
+        // Use the same conversion as the config file rectangles (72 points per inch)
+        let pixels_per_point = geometry::pixels_per_point(page_image.height(), page.height().value.round() as f64);
+        let pixels_per_inch = geometry::pixels_per_inch(pixels_per_point);
+
+        if cli.pick_region.is_empty() {
+
+            // Write the page with an inch grid, so pixel coordinates can be read from it in any image viewer
+            check_parent_folder(picker_path, "pick-regions", cli.create_dirs)?;
+            region_picker::draw_inch_grid(&page_image, pixels_per_inch).save(picker_path)?;
+
+            info!("The page image was written to {}, with a line every half inch ({:.1} pixels per inch).", picker_path.display(), pixels_per_inch);
+            info!("Run again with --pick-region x1,y1,x2,y2 (pixels in that image) for each region to get the config file contents.");
+
+        } else {
+
+            // Convert the pixel regions to a config file
+            let config = Config {
+                ignored_rectangles: cli.pick_region.iter()
+                    .map(|pixel_region| region_picker::pixels_to_inch_rectangle(&cli.pick_page.to_string(), *pixel_region, pixels_per_point))
+                    .collect(),
+                ignored_circles: Vec::new(),
+                ignored_polygons: Vec::new(),
+                settings: None,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&config)?);
+        }
+
+        return Ok(ComparisonReport::default());
+    }
+
+    // Otherwise, a second path is always given (the command line parser makes sure of that)
+    let original_pdf2_path = cli.original_pdf2_path.clone().expect("The second path is required.");
+
+
+    // If the second path is a folder of golden images, compare against those instead of a second document
+    if cli.baseline_dir {
+
+        if ! original_pdf2_path.is_dir() {
+            return Err(ComparisonError::new("The provided baseline folder does not exist.").into());
+        }
+
+        let report = baseline::compare_to_golden_images(&pdf_document_1, &original_pdf2_path, &build_render_config(cli.dpi1, &cli),
+            config_json.as_ref(), &comparison_settings, cli.resample.filter_type())?;
+
+        let differences_found = report.differences_found();
+
+        for page in &report.differing_pages {
+            info!("Page {} differs from its golden image.", page);
+        }
+
+        for page in &report.missing_goldens {
+            info!("Page {} has no golden image.", page);
+        }
+
+        for page in &report.orphan_goldens {
+            info!("The golden image for page {} has no matching page in the document.", page);
+        }
+
+        if differences_found {
+            verdict!("Differences were found.");
+
+            if !report.differing_pages.is_empty() {
+                info!("Differing pages: {}", format_page_ranges(&report.differing_pages));
+            }
+        } else {
+            verdict!("The PDF documents match.")
+        }
+
+        let result = ComparisonResult {
+            match_result: if differences_found { "Differences were found" } else { "Documents match" }.to_string(),
+            first_difference_page: report.first_difference_page(),
+            differing_pages: format_page_ranges(&report.differing_pages),
+            missing_baseline_pages: report.missing_goldens,
+            orphan_baseline_pages: report.orphan_goldens,
+            ..Default::default()
+        };
+
+        write_results(&cli, &result)?;
+
+        return Ok(ComparisonReport { differences_found, result });
+    }
+
+    let pdf_document_2 = load_document(&pdfium, &original_pdf2_path, document_passwords(&cli)[1], "second", "--password2 or --password")?;
+
+
+    // If only the form fields are being compared, compare their values and stop
+    if cli.form_fields {
+        let field_differences = form_fields::compare_field_values(
+            &form_fields::field_values(&pdf_document_1), &form_fields::field_values(&pdf_document_2));
+
+        for difference in &field_differences {
+            info!("Form field '{}' {:?}: {:?} -> {:?}", difference.name, difference.change, difference.value1, difference.value2);
+        }
+
+        let differences_found = !field_differences.is_empty();
+
+        if !differences_found {
+            verdict!("The form field values match.");
+        } else {
+            verdict!("Differences were found.");
+        }
+
+        let result = ComparisonResult {
+            match_result: if differences_found { "Differences were found" } else { "Documents match" }.to_string(),
+            form_field_differences: field_differences,
+            ..Default::default()
+        };
+
+        write_results(&cli, &result)?;
+
+        return Ok(ComparisonReport { differences_found, result });
+    }
+
+
+    // If a page is being looked for, compare it against every page of the second document and report the best match
+    if let Some(find_page) = cli.find_page {
+
+        let Ok(page1) = pdf_document_1.pages().get(find_page - 1) else {
+            return Err(ComparisonError::new(format!("The first document does not have a page {}.", find_page)).into());
+        };
+
+        let render_config = build_render_config(cli.dpi1, &cli);
+        let mut difference_ratios = vec![];
+
+        for (index, page2) in pdf_document_2.pages().iter().enumerate() {
+            let (image1, image2) = images::render_page_pair(&page1, &page2, &render_config, &render_config, cli.resample.filter_type())?;
+
+            // Use the rectangles of the page being looked for
+            let rectangles_to_ignore = shapes_to_ignore(config_json.as_ref(), &comparison_settings.ignored_bands, find_page, page1.height().value.round() as i32, &image1);
+
+            let differing_chunks = compare_page_images(&image1, &image2, &rectangles_to_ignore, &comparison_settings).len();
+            let total_chunks = image1.width().div_ceil(images::CHUNK_SIZE) * image1.height().div_ceil(images::CHUNK_SIZE);
+            let difference_ratio = differing_chunks as f64 / total_chunks as f64;
+
+            debug!("Page {} of the second document: {} of {} chunks differ", index + 1, differing_chunks, total_chunks);
+
+            difference_ratios.push(difference_ratio);
+        }
+
+        let found_page = best_matching_page(&difference_ratios);
+
+        match found_page {
+            Some(ref found) => info!("Page {} of the first document best matches page {} of the second document ({:.2}% of the page differs).",
+                find_page, found.page, found.difference_ratio * 100.0),
+            None => info!("The second document has no pages."),
+        }
+
+        let exact_match = found_page.as_ref().is_some_and(|found| found.difference_ratio == 0.0);
+
+        let result = ComparisonResult {
+            match_result: if exact_match { "Documents match" } else { "Differences were found" }.to_string(),
+            found_page,
+            ..Default::default()
+        };
+
+        write_results(&cli, &result)?;
+
+        return Ok(ComparisonReport { differences_found: !exact_match, result });
+    }
+
+
+    // Get the number of pages for each PDF document
+    let doc1_pages = pdf_document_1.pages().len();
+    let doc2_pages = pdf_document_2.pages().len();
+
+
+    // The pages asked for (and in strict mode, the pages of the config rectangles) have to be in the documents
+    if let Some(message) = page_reference_error(&cli, config_json.as_ref(), doc1_pages, doc2_pages) {
+        return Err(ComparisonError::new(message).into());
+    }
+
+    // If only the plan was asked for, print it and stop before comparing anything
+    if cli.plan {
+        print_comparison_plan(&cli, config_json.as_ref(), doc1_pages, doc2_pages);
+
+        return Ok(ComparisonReport::default());
+    }
+
+    // The indices of the pages of each document that are compared, in order: all of them, or only those that aren't blank.
+    // Leaving out the blank pages lines up the pages that follow an added blank page.
+    let (doc1_page_indices, doc2_page_indices): (Vec<u16>, Vec<u16>) = if cli.ignore_blank_pages {
+        let doc1_page_indices = non_blank_pages(&pdf_document_1, &build_render_config(cli.dpi1, &cli), "first")?;
+        let doc2_page_indices = non_blank_pages(&pdf_document_2, &build_render_config(cli.dpi2, &cli), "second")?;
+
+        debug!("Blank pages skipped: {} in the first document, {} in the second document.",
+                doc1_pages as usize - doc1_page_indices.len(), doc2_pages as usize - doc2_page_indices.len());
+
+        (doc1_page_indices, doc2_page_indices)
+    } else {
+        ((0..doc1_pages).collect(), (0..doc2_pages).collect())
+    };
+
+    // If the number of pages is different, the documents differ, but the pages they have in common are still compared
+    // (unless only a single page of each is compared)
+    if doc1_page_indices.len() != doc2_page_indices.len() && cli.page1.is_none() {
+        differences_in_number_of_pages = true;
+
+        debug!("The number of pages in the documents is different.");
+    }
+
+    // ... set pdf to image rendering options that will be applied to all pages...
+    // (a document with its own DPI gets its own render config)
+    let render_config1 = build_render_config(cli.dpi1, &cli);
+    let render_config2 = build_render_config(cli.dpi2, &cli);
+
+    // With 'coarse-first', both documents are first rendered small, whatever their DPI.  When matching pages are written as well,
+    // the small images would end up in the output, so every page is rendered at full resolution instead.
+    if cli.coarse_first && page_images_wanted {
+        debug!("Matching pages are written to the output, so the 'coarse-first' flag is ignored and every page is rendered at full resolution.");
+    }
+
+    let coarse_render_config = (cli.coarse_first && !page_images_wanted)
+        .then(|| build_render_config(None, &Cli { render_width: COARSE_RENDER_WIDTH, ..cli.clone() }));
+
+    // If rendered pages are being cached, the cache key covers everything that changes how a page is rendered
+    let render_settings = |dpi: Option<f32>| format!("{:?} {} {:?} {:?} {}", dpi, cli.render_width, cli.render, cli.object_type, cli.ignore_rotation);
+
+    let render_cache = match cli.render_cache {
+        Some(ref dir) => Some(render_cache::RenderCache::new(
+            dir,
+            [&original_pdf1_path, &original_pdf2_path],
+            [render_settings(cli.dpi1), render_settings(cli.dpi2)],
+        )?),
+        None => None,
+    };
+
+
+    // Create a variable to hold the PDF document if it's needed
+    let mut output_pdf = pdfium.create_new_pdf().unwrap();
+
+    // When splitting the output, keep track of the number of the output file being built
+    let mut output_file_number: u32 = 1;
+
+    // The pages of the output, stacked in one tall image, if asked for
+    let mut stacked_image = cli.single_image.as_deref().map(stacked_image::StackedImage::new);
+
+    // The colors for marking up the output file
+    let palette = match cli.highlight_color {
+        Some(color) => cli.palette.palette().with_highlight_color(color),
+        None => cli.palette.palette(),
+    };
+
+
+    // The pages both documents have (not counting blank pages, if they are ignored)
+    let common_pages = doc1_page_indices.len().min(doc2_page_indices.len()) as u16;
+
+    // Set when the comparison stops before the last page (e.g. 'stop', 'pages' or 'maxpages')
+    let mut stopped_early = false;
+
+    // Proceed with the comparison, unless the second document is reversed:
+    // its pages only line up with the first document if both have the same number of pages
+    if ! (differences_in_number_of_pages && cli.reverse2) {
+
+        // ... then iterate through the pages until reaching the end of the shortest document (or of the page range)
+        // The index stays that of the whole document, so rectangles still match the actual page numbers
+        let page_range = match cli.page1 {
+            Some(page1) => page1 - 1..page1,
+            None => cli.from.map_or(0, |from| from - 1)..cli.to.unwrap_or(common_pages),
+        };
+
+        for position in page_range {
+
+            // The index of the page in the first document (only different from the position when blank pages are left out)
+            let index = doc1_page_indices[position as usize];
+
+
+            if differences_found_in_page {
+                different_pages_count += 1;
+            }
+
+            // Reset variables
+            differences_found_in_page = false;
+
+            // If only odd or only even pages are being compared, skip the pages of the other parity (index is base zero)
+            if skipped_by_parity(&cli, index) {
+
+                // A skipped page still counts towards the 'pages' and 'maxpages' limits
+                if let Some(value) = cli.pages {
+                    if value == (index + 1) as i32 && differences_found_in_document {
+                        stopped_early = true;
+                        break;
+                    }
+                }
+
+                if let Some(value) = cli.maxpages {
+                    if value == (index + 1) as i32 {
+                        stopped_early = true;
+                        break;
+                    }
+                }
+
+                continue;
+            }
+
+            if index % 10 == 0 {
+                if index == 0{
+                    debug!("Comparison underway...");
+                } else {
+                    debug!("{:?} pages processed", (index));
+                }
+            }
+
+            // Create the objects for each of the pages to be compared
+            let mut doc1page = load_page(&pdf_document_1, index, "first")?;
+            // (page numbers reported are those of the first document)
+            let doc2_index = second_page_index(&cli, index, position, doc2_pages, &doc2_page_indices);
+            let mut doc2page = load_page(&pdf_document_2, doc2_index, "second")?;
+
+            // Compare the text of the pages, before any objects are hidden
+            if cli.text {
+                let text_difference = text_layer::compare_page_text((index + 1) as i32,
+                    &text_layer::page_text_lines(&doc1page)?, &text_layer::page_text_lines(&doc2page)?);
+
+                if let Some(text_difference) = text_difference {
+                    if let Some(ref note) = text_difference.note {
+                        info!("Page {} text: {}", text_difference.page, note);
+                    }
+
+                    for line in &text_difference.removed {
+                        info!("Page {} text removed: {}", text_difference.page, line);
+                    }
+
+                    for line in &text_difference.added {
+                        info!("Page {} text added: {}", text_difference.page, line);
+                    }
+
+                    text_differences.push(text_difference);
+                }
+            }
+
+            // If only one type of object is being compared, hide all the others (this doesn't change the documents)
+            if let Some(object_type) = cli.object_type {
+                images::keep_only_objects_of_type(&mut doc1page, object_type.page_object_type())?;
+                images::keep_only_objects_of_type(&mut doc2page, object_type.page_object_type())?;
+            }
+
+            // If rotation is ignored, undo any rotation set on the pages, so both are compared in their stored orientation
+            // (this is only done in memory, the documents are not changed)
+            if cli.ignore_rotation {
+                if doc1page.rotation()? != doc2page.rotation()? {
+                    debug!("Page {:?} is rotated differently in the two docs ({:?} and {:?}); the rotation is ignored.", (index + 1), doc1page.rotation()?, doc2page.rotation()?);
+                }
+
+                doc1page.set_rotation(PdfPageRenderRotation::None);
+                doc2page.set_rotation(PdfPageRenderRotation::None);
+            }
+
+            // Get the dimensions of the pages
+            let doc1width = doc1page.width();
+            let doc1height = doc1page.height();
+            let doc2width = doc2page.width();
+            let doc2height = doc2page.height();
+
+            let page_height_integer_in_points = doc1height.value.round() as i32;
+
+
+            // If the size of the pages is different (beyond a small tolerance, as page dimensions are floating point)
+            if geometry::page_sizes_differ((doc1width.value, doc1height.value), (doc2width.value, doc2height.value), cli.size_epsilon) {
+
+                let size_message = format!("Page {} size differs: doc1 {:.0}x{:.0} vs doc2 {:.0}x{:.0} points",
+                    index + 1, doc1width.value, doc1height.value, doc2width.value, doc2height.value);
+
+                info!("{}.  Ending the comparison.", size_message);
+                
+                differences_found_in_document = true;
+                differing_pages.push((index + 1) as i32);
+                page_results.push(PageResult { page: (index + 1) as i32, differs: true, differing_chunks: 0, bounding_box: None, bounding_box_points: None, difference_percentage: 100.0, all_chunks_ignored: false, chunk_changes: ChunkChangeCounts::default(), ssim: None });
+
+                // The pages compared so far are still written to the output file, followed by both versions of this page,
+                // labeled, to show where the layout changed
+                if (cli.output.is_some() || cli.image_dir.is_some() || stacked_image.is_some()) && different_pages_count < 500 {
+                    let combined_image = images::combine_side_by_side(
+                        &images::render_page(&doc1page, &render_config1)?,
+                        &images::render_page(&doc2page, &render_config2)?,
+                        cli.gutter_width,
+                        cli.separator_color,
+                    );
+
+                    if let Some(ref image_dir) = cli.image_dir {
+                        save_page_image(image_dir, &combined_image, index + 1);
+                    }
+
+                    if let Some(ref mut stacked_image) = stacked_image {
+                        stacked_image.add(&combined_image)?;
+                    }
+
+                    if cli.output.is_some() {
+                        add_output_page(&pdfium, &mut output_pdf, &mut output_file_number, &cli, combined_image, index + 1, Some((&size_message, 0)))?;
+                    }
+                }
+
+                // Break out of the for loop and end this.  Comparing pixels of pages that are different sizes ends badly.
+                stopped_early = true;
+                break;
+            }
+
+
+            // How long each step takes on this page, for the debug output
+            let mut timings = timing::PageTimings { page: index + 1, ..Default::default() };
+
+            // Create an image of the current page from each document, at a common size
+            let render_started = Instant::now();
+
+            // With 'coarse-first', a page that matches at a low resolution isn't rendered again: the small images are used from here on
+            let coarse_images = match coarse_render_config {
+                Some(ref coarse_render_config) => {
+                    let (coarse_image1, coarse_image2) = images::render_page_pair(&doc1page, &doc2page, coarse_render_config, coarse_render_config, cli.resample.filter_type())?;
+                    let coarse_shapes_to_ignore = shapes_to_ignore(config_json.as_ref(), &comparison_settings.ignored_bands, index + 1, page_height_integer_in_points, &coarse_image1);
+
+                    if compare_page_images(&coarse_image1, &coarse_image2, &coarse_shapes_to_ignore, &comparison_settings).is_empty() {
+                        Some((coarse_image1, coarse_image2))
+                    } else {
+                        debug!("Page {:?} differs at {} pixels, so it is rendered again at full resolution.", (index + 1), COARSE_RENDER_WIDTH);
+
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            let (mut image1, mut image2) = match coarse_images {
+                Some(coarse_images) => coarse_images,
+                None => match render_cache {
+                    Some(ref cache) => images::match_image_sizes(
+                        cache.render_page(0, &doc1page, index, &render_config1)?,
+                        cache.render_page(1, &doc2page, doc2_index, &render_config2)?,
+                        cli.resample.filter_type()),
+                    None => images::render_page_pair(&doc1page, &doc2page, &render_config1, &render_config2, cli.resample.filter_type())?,
+                },
+            };
+
+            timings.render += render_started.elapsed();
+
+            // Level scanned pages that went through the scanner at a slight angle
+            if cli.deskew {
+                let (doc1_degrees, doc2_degrees);
+
+                (image1, doc1_degrees) = deskew::deskew(image1);
+                (image2, doc2_degrees) = deskew::deskew(image2);
+
+                debug!("Page {:?} skew: {:.1} degrees (doc1), {:.1} degrees (doc2)", (index + 1), doc1_degrees, doc2_degrees);
+
+                skew_angles.push(PageSkew {
+                    page: (index + 1) as i32,
+                    doc1_degrees,
+                    doc2_degrees,
+                });
+            }
+
+            // Line up a page that was scanned slightly rotated or shifted with the page of the first document
+            if cli.register {
+                let registration;
+
+                (image2, registration) = register::register(&image1, image2);
+
+                debug!("Page {:?} registration: doc2 rotated by {:.1} degrees and shifted by ({}, {}) pixels", (index + 1), registration.degrees, -registration.shift_x, -registration.shift_y);
+            }
+
+
+            // Define the current page number (index is base zero)
+            let page_val = index + 1;
+
+            // Print the geometry of the page, to help troubleshoot rectangles that land in the wrong place
+            if cli.dump_geometry {
+                info!("{}", page_geometry_line(&cli, page_val, (doc1width.value, doc1height.value), (doc2width.value, doc2height.value), image1.dimensions()));
+            }
+
+            // Check to see if there are rectangles that need to be ignored in this page
+            // This may be empty if there is no config json or it has no rectangles for this page
+            let current_page_rectangles_to_ignore = shapes_to_ignore(config_json.as_ref(), &comparison_settings.ignored_bands, page_val, page_height_integer_in_points, &image1);
+
+            // Point out config file shapes that can't exclude what was meant (e.g. a typo putting a rectangle far off the page)
+            if let Some(ref config) = config_json {
+                for warning in config.misplaced_shapes(page_val.to_string().as_str(), page_height_integer_in_points, image1.width(), image1.height()) {
+                    warn!("Warning: {}", warning);
+
+                    shape_warnings.push(warning);
+                }
+            }
+
+            // Report a shift in background tone, which the comparison will ignore
+            if cli.normalize_background && log_enabled!(Level::Debug) {
+                if let Some(shift) = images::background_shift(&image1, &image2) {
+                    debug!("Page {:?} background colors differ by {:?} (doc1 - doc2); the second page is shifted to match.", page_val, shift);
+                }
+            }
+
+            // Compare the images of the two pages, sending in ignored areas (if any)
+            let compare_started = Instant::now();
+            let page_differences_vector = compare_page_images(&image1, &image2, &current_page_rectangles_to_ignore, &comparison_settings);
+            timings.compare += compare_started.elapsed();
+
+            // Separate out any differences that were previously acknowledged, so they do not affect the verdict
+            let (mut page_differences_vector, acknowledged_differences_vector) = match &acknowledgements {
+                Some(temporary_acknowledgements) => temporary_acknowledgements.split_chunks(page_val as i32, page_differences_vector, images::CHUNK_SIZE),
+                None => (page_differences_vector, Vec::new()),
+            };
+
+            // If only a few chunks differ, confirm the differences at a higher resolution.
+            // Rendering noise tends to vanish at a higher resolution, while real changes persist.
+            if let Some(confirm_limit) = cli.confirm_small {
+                if !page_differences_vector.is_empty() && page_differences_vector.len() <= confirm_limit as usize {
+
+                    let confirm_render_config = build_render_config(Some(cli.confirm_dpi), &cli);
+                    let render_started = Instant::now();
+                    let (confirm_image1, confirm_image2) = images::render_page_pair(&doc1page, &doc2page, &confirm_render_config, &confirm_render_config, cli.resample.filter_type())?;
+                    timings.render += render_started.elapsed();
+
+                    let confirm_rectangles_to_ignore = shapes_to_ignore(config_json.as_ref(), &comparison_settings.ignored_bands, page_val, page_height_integer_in_points, &confirm_image1);
+
+                    let compare_started = Instant::now();
+                    let confirm_differences_vector = compare_page_images(&confirm_image1, &confirm_image2, &confirm_rectangles_to_ignore, &comparison_settings);
+                    timings.compare += compare_started.elapsed();
+
+                    // Scale the confirmed chunks back onto the chunks of the standard resolution, so acknowledged regions still apply
+                    let confirm_differences_vector = images::scale_chunks(&confirm_differences_vector, confirm_image1.dimensions(), image1.dimensions());
+
+                    let confirm_differences_vector = match &acknowledgements {
+                        Some(temporary_acknowledgements) => temporary_acknowledgements.split_chunks(page_val as i32, confirm_differences_vector, images::CHUNK_SIZE).0,
+                        None => confirm_differences_vector,
+                    };
+
+                    if confirm_differences_vector.is_empty() {
+                        debug!("The small differences on page {:?} were not confirmed at {} DPI, so the page is treated as matching.", page_val, cli.confirm_dpi);
+
+                        page_differences_vector.clear();
+                    } else {
+                        debug!("The small differences on page {:?} were confirmed at {} DPI.", page_val, cli.confirm_dpi);
+                    }
+                }
+            }
+
+            if !acknowledged_differences_vector.is_empty() {
+                debug!("Acknowledged differences for page {:?}: {:?}", page_val, acknowledged_differences_vector);
+            }
+
+            // How structurally similar the pages are; similar enough pages match, even if a few chunks differ
+            let page_ssim = images::compute_ssim(&image1, &image2, &current_page_rectangles_to_ignore);
+
+            if log_enabled!(Level::Debug) {
+                if let Some(ssim) = page_ssim {
+                    debug!("Page {:?} SSIM: {:.4}", page_val, ssim);
+                }
+            }
+
+            if let (Some(threshold), Some(ssim)) = (cli.ssim_threshold, page_ssim) {
+                if ssim >= threshold && !page_differences_vector.is_empty() {
+                    debug!("Page {:?} is treated as matching, as its SSIM is at least {}.", page_val, threshold);
+
+                    page_differences_vector.clear();
+                }
+            }
+
+            histogram.add_page(page_differences_vector.len());
+
+            // How much of the page differs, out of the chunks that were compared
+            let compared_chunks = images::count_compared_chunks(image1.width(), image1.height(), &current_page_rectangles_to_ignore);
+            compared_chunks_in_document += compared_chunks;
+
+            let difference_percentage = if compared_chunks == 0 {
+                0.0
+            } else {
+                page_differences_vector.len() as f64 * 100.0 / compared_chunks as f64
+            };
+
+            if log_enabled!(Level::Debug) {
+                if compared_chunks == 0 {
+                    debug!("Page {:?}: all chunks are ignored, nothing was compared.", page_val);
+                } else {
+                    debug!("Page {:?}: {:.2}% of the compared chunks differ.", page_val, difference_percentage);
+                }
+            }
+
+            // Tell removed content from added content, by which page has content where they differ
+            let chunk_changes = images::classify_chunks(&image1, &image2, &page_differences_vector);
+            let page_chunk_changes = ChunkChangeCounts::new(&chunk_changes);
+
+            document_chunk_changes.add(&page_chunk_changes);
+
+            // The box around the differences, in pixels and in points of the first document's page (for annotating the original PDF)
+            let bounding_box = regions::bounding_box(&page_differences_vector, images::CHUNK_SIZE, image1.width(), image1.height());
+            let bounding_box_points = bounding_box.map(|region| {
+                regions::region_to_points(&region, geometry::pixels_per_point(image1.height(), doc1height.value as f64), doc1height.value as f64)
+            });
+
+            page_results.push(PageResult {
+                page: page_val as i32,
+                differs: !page_differences_vector.is_empty(),
+                differing_chunks: page_differences_vector.len(),
+                bounding_box,
+                bounding_box_points,
+                difference_percentage,
+                all_chunks_ignored: compared_chunks == 0,
+                chunk_changes: page_chunk_changes,
+                ssim: page_ssim,
+            });
+
+            // Set the differences_found variables to true if the vector is not empty
+            if !page_differences_vector.is_empty(){
+                differences_found_in_document = true;
+                differences_found_in_page = true;
+                differing_pages.push(page_val as i32);
+
+                debug!("page_differences_vector for page {:?}: {:?}", page_val, page_differences_vector);
+
+                // Check whether some of the differences are really content that moved
+                if cli.detect_moves {
+                    let page_regions = regions::merge_chunks(&page_differences_vector, images::CHUNK_SIZE, image1.width(), image1.height());
+                    let page_moves = regions::detect_moves(&image1, &image2, &page_regions, images::CHUNK_SIZE, page_val as i32);
+
+                    for page_move in &page_moves {
+                        info!("Content moved on page {}: the region at ({}, {}) moved by ({}, {}) pixels.", page_val,
+                            page_move.from.left, page_move.from.top, page_move.displacement[0], page_move.displacement[1]);
+                    }
+
+                    moved_content.extend(page_moves);
+                }
+
+            }
+
+
+
+            /******************************************************
+            If GIFs are desired, write a GIF flipping between the two page images
+            ******************************************************/
+            if let Some(ref gif_dir) = cli.gif_dir {
+                if differences_found_in_page {
+                    let gif_path = gif_dir.join(format!("page-{:04}.gif", page_val));
+
+                    if let Err(e) = images::save_flip_gif(&gif_path, &image1, &image2, cli.gif_delay) {
+                        error!("Error writing GIF file {}: {}", gif_path.display(), e);
+                    }
+                }
+            }
+
+
+            /******************************************************
+            If a results file is desired, highlight the differences in the images, and add to a results file
+            ******************************************************/
+
+            let output_started = Instant::now();
+
+            // If the user used the 'output', 'image-dir' or 'single-image' argument
+            if cli.output.is_some() || cli.image_dir.is_some() || stacked_image.is_some() {
+            
+
+                // create a boolean for the below conditional
+                let mut max_pages_is_less_than_500: bool = false;
+
+                // If the user used the max pages flag and gave it a value
+                if let Some(value) = cli.maxpages {
+
+                    // If the value is less than 500
+                    if value < 500{
+
+                        max_pages_is_less_than_500 = true;
+                    }
+
+                }
+
+
+
+                // If there are fewer than 500 different pages so far AND
+                // (the current page has differences OR (justdiff is off AND (the total document is less than 500 pages OR the maxpages is less than 500))
+                // The condition for < 500 pages was added due to memory constraints.  Issues could occur at 1000+ pages depending on available system resources.
+                // Essentially, we do not want to make an output file greater than 500 pages so memory issues can be prevented.
+                if (differences_found_in_page || (!cli.justdiff && (doc1_pages < 500 || max_pages_is_less_than_500))) && different_pages_count < 500 {
+
+                    let combined_image = if cli.overlay {
+
+                        // Blend both pages into a single image: content only in the first document in red, only in the second in blue
+                        let mut overlay_image = images::overlay_pages(&image1, &image2, &comparison_settings);
+
+                        // If there are rectangles that were ignored on this page, draw them
+                        if !current_page_rectangles_to_ignore.is_empty() {
+                            images::draw_ignored_shapes_mut(&mut overlay_image, Some(&current_page_rectangles_to_ignore), &palette, cli.border_thickness);
+                        }
+
+                        overlay_image
+
+                    } else {
+
+                        // Take actions to highlight differences and create an output document
+                        // Start from the original, non-highlighted page images
+                        let mut doc1_page_completed_image = image1;
+                        let mut doc2_page_completed_image = image2;
+
+                        // Highlight the differences within the images
+                        // If differences were found in page
+                        if differences_found_in_page {
+
+                            // Count the separate areas of differences, to check against the 'max-boxes' limit
+                            if has_too_many_boxes(&page_differences_vector, cli.max_boxes, doc1_page_completed_image.dimensions()) {
+                                debug!("Page {:?} has more than {:?} areas of differences, marking it as heavily changed.", page_val, cli.max_boxes.unwrap_or_default());
+
+                                images::draw_changed_banner_mut(&mut doc1_page_completed_image, &palette);
+                                images::draw_changed_banner_mut(&mut doc2_page_completed_image, &palette);
+                            } else {
+                                // The page images are not needed anymore, so they are highlighted in place rather than copied
+                                images::highlight_classified_chunks_mut(&mut doc1_page_completed_image, &page_differences_vector, &chunk_changes, &palette);
+                                images::highlight_classified_chunks_mut(&mut doc2_page_completed_image, &page_differences_vector, &chunk_changes, &palette);
+                            }
+                        }
+
+                        // Show any acknowledged differences dimmed
+                        if !acknowledged_differences_vector.is_empty() {
+                            images::dim_chunks_mut(&mut doc1_page_completed_image, &acknowledged_differences_vector);
+                            images::dim_chunks_mut(&mut doc2_page_completed_image, &acknowledged_differences_vector);
+                        }
+
+                        // If there are rectangles that were ignored on this page, draw them
+                        if !current_page_rectangles_to_ignore.is_empty(){
+                            images::draw_ignored_shapes_mut(&mut doc1_page_completed_image, Some(&current_page_rectangles_to_ignore), &palette, cli.border_thickness);
+                            images::draw_ignored_shapes_mut(&mut doc2_page_completed_image, Some(&current_page_rectangles_to_ignore), &palette, cli.border_thickness);
+                        }
+
+
+                        // Create a single image that contains both highlighted images, as well as a separator
+                        // that marks the rows with differences
+                        let mut combined_image = images::combine_side_by_side(&doc1_page_completed_image, &doc2_page_completed_image, cli.gutter_width, cli.separator_color);
+
+                        images::mark_differences_in_gutter_mut(&mut combined_image, doc1_page_completed_image.width(), cli.gutter_width,
+                            &page_differences_vector, &chunk_changes, &palette);
+                        combined_image
+                    };
+
+                    // Write it to the image folder
+                    if let Some(ref image_dir) = cli.image_dir {
+                        save_page_image(image_dir, &combined_image, page_val);
+                    }
+
+                    // Add it below the pages so far in the single image
+                    if let Some(ref mut stacked_image) = stacked_image {
+                        stacked_image.add(&combined_image)?;
+                    }
+
+                    // Add it to the output PDF document
+                    if cli.output.is_some() {
+                        add_output_page(&pdfium, &mut output_pdf, &mut output_file_number, &cli, combined_image, page_val, None)?;
+                    }
+
+                }
+
+            }        
+
+            timings.output += output_started.elapsed();
+            page_timings.push(timings);
+
+            /******************************************************
+            If stop is true and differences have been found, stop the comparison.
+            ******************************************************/
+            if cli.stop && differences_found_in_document{
+                
+                // Break out of the for loop and finish up
+                stopped_early = true;
+                break;
+            }
+            
+
+            /******************************************************
+            If the current page is equal to the 'pages' value, stop if there have been differences.
+            ******************************************************/
+            // If the user used the pages flag and gave it a value
+            if let Some(value) = cli.pages {
+
+                // If the index (page) is the same as the page the user specified AND differences have been found in the document
+                if (value == (index + 1) as i32) && differences_found_in_document{
+
+                    debug!("The 'pages' flag was set with value {}, and differences have been found, so the comparison is stopping.", value);
+
+                    // Break out of the for loop and finish up
+                    stopped_early = true;
+                    break;
+                }
+
+            }
+
+            /******************************************************
+            If the current page is the maxpages value, stop the comparison.
+            ******************************************************/
+            // If the user used the max pages flag and gave it a value
+            if let Some(value) = cli.maxpages {
+
+                // If the index (page) is the same as the page the user specified
+                if value == (index + 1) as i32{
+
+                    debug!("The 'maxpages' flag was set with value {value}, so the comparison is stopping now.");
+
+                    // Break out of the for loop and finish up
+                    stopped_early = true;
+                    break;
+                }
+
+            }
+
+            
+
+        } // End of the for loop iterating through each page
+
+        // Pages that only exist in the longer document were added (or removed): show them next to a blank panel
+        // (unless the page range ends before them)
+        if differences_in_number_of_pages && !stopped_early && cli.to.is_none() {
+            let (longer_document, longer_render_config, longer_page_indices, only_in) = if doc1_page_indices.len() > doc2_page_indices.len() {
+                (&pdf_document_1, &render_config1, &doc1_page_indices, "first")
+            } else {
+                (&pdf_document_2, &render_config2, &doc2_page_indices, "second")
+            };
+
+            for &index in &longer_page_indices[common_pages as usize..] {
+                let page_val = index + 1;
+
+                if cli.maxpages.is_some_and(|max_pages| page_val as i32 > max_pages) {
+                    break;
+                }
+
+                info!("Page {} only exists in the {} document.", page_val, only_in);
+
+                differing_pages.push(page_val as i32);
+                page_results.push(PageResult { page: page_val as i32, differs: true, differing_chunks: 0, bounding_box: None, bounding_box_points: None, difference_percentage: 100.0, all_chunks_ignored: false, chunk_changes: ChunkChangeCounts::default(), ssim: None });
+
+                if (cli.output.is_some() || cli.image_dir.is_some() || stacked_image.is_some()) && different_pages_count < 500 {
+                    let page_image = images::render_page(&load_page(longer_document, index, only_in)?, longer_render_config)?;
+                    let blank_panel = RgbaImage::from_pixel(page_image.width(), page_image.height(), Rgba([230, 230, 230, 255]));
+
+                    // The blank panel takes the place of the document that doesn't have the page, and is labeled
+                    let (combined_image, label_x) = if doc1_page_indices.len() > doc2_page_indices.len() {
+                        (images::combine_side_by_side(&page_image, &blank_panel, cli.gutter_width, cli.separator_color), page_image.width() + cli.gutter_width)
+                    } else {
+                        (images::combine_side_by_side(&blank_panel, &page_image, cli.gutter_width, cli.separator_color), 0)
+                    };
+
+                    let label = format!("Page {} only exists in the {} document", page_val, only_in);
+
+                    if let Some(ref image_dir) = cli.image_dir {
+                        save_page_image(image_dir, &combined_image, page_val);
+                    }
+
+                    if let Some(ref mut stacked_image) = stacked_image {
+                        stacked_image.add(&combined_image)?;
+                    }
+
+                    if cli.output.is_some() {
+                        add_output_page(&pdfium, &mut output_pdf, &mut output_file_number, &cli, combined_image, page_val, Some((&label, label_x)))?;
+                    }
+
+                    different_pages_count += 1;
+                }
+
+                // The first added (or removed) page is a difference, so 'stop' ends the comparison here
+                if cli.stop {
+                    break;
+                }
+            }
+        }
+
+    }
+
+    // The first page that only exists in the longer document is a difference, if no earlier page differed
+    let first_extra_page = differences_in_number_of_pages.then(|| {
+        let longer_page_indices = if doc1_page_indices.len() > doc2_page_indices.len() { &doc1_page_indices } else { &doc2_page_indices };
+
+        longer_page_indices[common_pages as usize] as i32 + 1
+    });
+
+    // Clean up, the comparison is over.
+
+    // If the user used the 'output' argument
+    if let Some(ref _value) = cli.output {
+
+        // ...and there is at least one page in the output file
+        // (consider combinations like 'justdiff' and files matching)
+        if output_pdf.pages().get(0).is_ok() {
+
+            // Write the document to disk
+            if let Some(ref path) = cli.output {
+                let saving_started = Instant::now();
+
+                if cli.split_output.is_some() {
+                    output_pdf.save_to_file(&numbered_output_path(path, output_file_number))?;
+                } else {
+                    output_pdf.save_to_file(path)?;
+                }
+
+                saving_time = saving_started.elapsed();
+            } else {
+                error!("There is an issue with the file path provided as the output.");
+            }
+        
+        }
+    }
+
+
+    // Write the pages that are left of the single image (more than one image if they are too tall for one)
+    if let Some(stacked_image) = stacked_image {
+        let saving_started = Instant::now();
+        let written_paths = stacked_image.finish()?;
+        saving_time += saving_started.elapsed();
+
+        if written_paths.is_empty() {
+            info!("No pages were added to the single image, so {} was not written.", cli.single_image.as_deref().unwrap_or(Path::new("")).display());
+        } else if written_paths.len() > 1 {
+            info!("The pages were too tall for a single image, so they were written to {} images: {}", written_paths.len(),
+                written_paths.iter().map(|path| path.display().to_string()).collect::<Vec<String>>().join(", "));
+        }
+    }
+
+
+    if log_enabled!(Level::Debug) {
+        if let Some(ref cache) = render_cache {
+            debug!("Render cache: {} pages loaded from the cache, {} pages rendered.", cache.hits.get(), cache.misses.get());
+        }
+
+        timing::print_summary(&page_timings, saving_time);
+    }
+
+    // A few differing chunks can be allowed by a fail threshold: they are still in the output and result files,
+    // but the documents are treated as matching.  Pages that differ in size or number always fail the comparison.
+    let differing_chunks_in_document: usize = page_results.iter().map(|page_result| page_result.differing_chunks).sum();
+    let different_page_sizes = page_results.iter().any(|page_result| page_result.differs && page_result.differing_chunks == 0);
+
+    let within_fail_threshold = !differences_in_number_of_pages && !different_page_sizes && (
+        cli.fail_threshold.is_some_and(|threshold| differing_chunks_in_document <= threshold) ||
+        cli.fail_percent.is_some_and(|percent| compared_chunks_in_document == 0 ||
+            differing_chunks_in_document as f64 * 100.0 / compared_chunks_in_document as f64 <= percent)
+    );
+
+    let differences_found = (differences_found_in_document || differences_in_number_of_pages) && !within_fail_threshold;
+
+    if differences_found {
+
+        verdict!("Differences were found.");
+
+        if !differing_pages.is_empty() {
+            info!("Differing pages: {}", format_page_ranges(&differing_pages));
+        }
+        
+    } else if differences_found_in_document {
+
+        verdict!("The PDF documents match: the {} differing chunks are within the fail threshold.", differing_chunks_in_document);
+
+        if !differing_pages.is_empty() {
+            info!("Differing pages: {}", format_page_ranges(&differing_pages));
+        }
+
+    } else {
+
+        verdict!("The PDF documents match.")
+
+    }
+    
+
+    // The average difference of the compared pages
+    let difference_percentage = if page_results.is_empty() {
+        None
+    } else {
+        Some(page_results.iter().map(|page_result| page_result.difference_percentage).sum::<f64>() / page_results.len() as f64)
+    };
+
+    // The average structural similarity of the compared pages
+    let page_ssims: Vec<f64> = page_results.iter().filter_map(|page_result| page_result.ssim).collect();
+
+    let ssim = if page_ssims.is_empty() {
+        None
+    } else {
+        Some(page_ssims.iter().sum::<f64>() / page_ssims.len() as f64)
+    };
+
+    if log_enabled!(Level::Debug) {
+        if let Some(percentage) = difference_percentage {
+            debug!("On average, {:.2}% of the compared chunks of a page differ.", percentage);
+        }
+    }
+
+    // Set the text you want to write to the JSON
+    let result_text: String = if differences_found {
+
+        "Differences were found".to_string()
+
+    } else {
+
+        "Documents match".to_string()
+
+    };
+
+    // Create a variable to hold the text result of the comparison
+    let result = ComparisonResult {
+        match_result: result_text,
+        first_difference_page: first_difference_page(&differing_pages, first_extra_page),
+        moved_content,
+        histogram: Some(histogram),
+        skew_angles,
+        shape_warnings,
+        text_differences,
+        chunk_changes: Some(document_chunk_changes),
+        differing_pages: format_page_ranges(&differing_pages),
+        difference_percentage,
+        ssim,
+        pages: page_results,
+        ..Default::default()
+    };
+
+    // If a result text (json) file is desired, write to it.
+    write_results(&cli, &result)?;
+
+    
+    Ok(ComparisonReport { differences_found, result })
+
+}
+
+
+// Add a page showing the combined image to the output PDF document, scaled to the width of an output page.
+// If the output is being split and the current output document is full, it is written first and a new one is started.
+// An optional label is written at the top of the page, starting at the given x position (in pixels of the combined image).
+fn add_output_page<'a>(
+    pdfium: &'a Pdfium,
+    output_pdf: &mut PdfDocument<'a>,
+    output_file_number: &mut u32,
+    cli: &Cli,
+    combined_image: RgbaImage,
+    page_val: u16,
+    label: Option<(&str, u32)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+
+    // Calculate the scaling factor based on the desired width
+    let mut scale_factor = geometry::output_scale_factor(combined_image.width(), cli.output_width_inches);
+
+    // A very tall page would be larger than a PDF page can be, so scale it down to fit
+    let limited_scale_factor = geometry::limit_scale_to_pdf_page_size(scale_factor, combined_image.width(), combined_image.height());
+
+    if limited_scale_factor < scale_factor {
+        info!("Page {} is too tall for a PDF page at full width ({:.0} inches), so it was scaled down to fit in the output file.",
+            page_val, combined_image.height() as f64 * scale_factor as f64 / geometry::POINTS_PER_INCH);
+
+        scale_factor = limited_scale_factor;
+    }
+
+    // Apply the scaling factor to image sizes and positions
+    let width = combined_image.width() as f32 * scale_factor;
+    let height = combined_image.height() as f32 * scale_factor;
+    
+    let paper_size = PdfPagePaperSize::Custom(PdfPoints::new(width), PdfPoints::new(height));
+
+    let image_x_position_in_points = PdfPoints::new(0.0);
+    let image_y_position_in_points = PdfPoints::new(0.0);
+
+
+    // If the output is being split and the current output document is full, write it and start a new one
+    if let Some(path) = &cli.output {
+        if output_file_is_full(output_pdf.pages().len(), cli.split_output) {
+            output_pdf.save_to_file(&numbered_output_path(path, *output_file_number))?;
+
+            *output_pdf = pdfium.create_new_pdf()?;
+            *output_file_number += 1;
+        }
+    }
+
+    // The font of the label belongs to the document, so it's added before the page
+    let label_font = label.map(|_| output_pdf.fonts_mut().helvetica());
+
+    // Add a page to the output pdf document
+    let page = output_pdf.pages_mut().create_page_at_end(paper_size);
+
+    // Check to see if the page is a page, since it was actually wrapped in a result enum
+    if let Ok(mut page) = page {
+
+        // Get the combined image width
+        let combined_image_width = combined_image.width() as f32 * scale_factor;
+
+        // Convert the combined image into the type acceptable for writing to the page
+        let dynamic_combined_image = DynamicImage::ImageRgba8(combined_image);
+
+        // Make a PDF document object using the combined image
+        let mut object = PdfPageImageObject::new_with_width(
+            output_pdf,
+            &dynamic_combined_image,
+            PdfPoints::new(combined_image_width),
+        )?;
+
+        // Describe the placement of the object (start from 0,0 as it's a single image)
+        object.translate(image_x_position_in_points, image_y_position_in_points)?;
+
+        // Add the combined image to the destination PDF page.
+        page.objects_mut().add_image_object(object)?;
+
+        // Write the label over the image, near its top (PDF y coordinates start at the bottom of the page)
+        if let (Some((text, label_x)), Some(font)) = (label, label_font) {
+            page.objects_mut().create_text_object(
+                PdfPoints::new(label_x as f32 * scale_factor + 12.0),
+                PdfPoints::new(height - 28.0),
+                text,
+                font,
+                PdfPoints::new(16.0),
+            )?;
+        }
+
+    } else if let Err(e) = page {
+
+        error!("Something went wrong when adding page {} to the output PDF document: {}", page_val, e);
+    }
+
+    Ok(())
+}
+
+
+// Bind to the pdfium library, looking in these folders in order:
+// the 'pdfium-path' flag, the PDFIUM_LIB_PATH environment variable, the folder of the executable, and the current folder
+fn bind_pdfium(pdfium_path: Option<&Path>) -> Result<Box<dyn PdfiumLibraryBindings>, ComparisonError> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+
+    if let Some(path) = pdfium_path {
+        folders.push(path.to_path_buf());
+    }
+
+    if let Some(path) = std::env::var_os("PDFIUM_LIB_PATH") {
+        folders.push(PathBuf::from(path));
+    }
+
+    if let Some(path) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        folders.push(path);
+    }
+
+    folders.push(PathBuf::from("./"));
+
+    for folder in &folders {
+        let library_path = folder.join(Pdfium::pdfium_platform_library_name());
+
+        match Pdfium::bind_to_library(library_path.to_string_lossy()) {
+            Ok(bindings) => {
+                debug!("The pdfium library was loaded from {}", folder.display());
+
+                return Ok(bindings);
+            },
+            Err(e) => {
+                // Only the reason the library didn't load, not the whole error structure
+                let reason = match e {
+                    PdfiumError::LoadLibraryError(error) => error.to_string(),
+                    other => format!("{:?}", other),
+                };
+
+                debug!("The pdfium library could not be loaded from {}: {}", library_path.display(), reason);
+            },
+        }
+    }
+
+    Err(ComparisonError::new(format!("Could not load the pdfium library ({}) from {}; download it or set --pdfium-path.\n\
+        The PDFIUM_LIB_PATH environment variable can also be set to the folder that has the library.",
+        Pdfium::pdfium_platform_library_name().to_string_lossy(),
+        folders.iter().map(|folder| folder.display().to_string()).collect::<Vec<_>>().join(", "))))
+}
+
+
+// Write the combined image of a page to the image folder, as page-0001.png, page-0002.png, ...
+fn save_page_image(image_dir: &Path, combined_image: &RgbaImage, page_val: u16) {
+    let image_path = image_dir.join(format!("page-{:04}.png", page_val));
+
+    if let Err(e) = combined_image.save(&image_path) {
+        error!("Error writing image file {}: {}", image_path.display(), e);
+    }
+}
+
+
+// Load a pdf document (from standard input if the path is '-'), with its password if it has one.
+// If the document can't be decrypted or loaded, the error says which document it is.
+fn load_document<'a>(pdfium: &'a Pdfium, path: &Path, password: Option<&'a str>, description: &str, password_flag: &str) -> Result<PdfDocument<'a>, Box<dyn std::error::Error>> {
+    let document = if is_stdin(path) {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+
+        pdfium.load_pdf_from_byte_vec(bytes, password)
+    } else {
+        pdfium.load_pdf_from_file(path, password)
+    };
+
+    document.map_err(|error| document_load_error(error, password.is_some(), path, description, password_flag))
+}
+
+
+// The error for a document that couldn't be loaded, naming the document (its description and path) and what to do about it
+fn document_load_error(error: PdfiumError, password_given: bool, path: &Path, description: &str, password_flag: &str) -> Box<dyn std::error::Error> {
+    match error {
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError) => {
+            if password_given {
+                ComparisonError::new(format!("The password for the {} document ({}) is not correct.", description, path.display())).into()
+            } else {
+                ComparisonError::new(format!("The {} document ({}) is password protected; supply its password with {}.", description, path.display(), password_flag)).into()
+            }
+        }
+        // A file that can't be read at all (e.g. it doesn't exist) is reported as is
+        PdfiumError::IoError(e) => e.into(),
+        e => ComparisonError::corrupt(format!("The {} document ({}) could not be loaded ({:?}); the file may be corrupt, truncated or not a PDF.", description, path.display(), e)).into(),
+    }
+}
+
+
+// The index of the page of the second document compared with the page at this index (and position among the pages compared) of the first document
+fn second_page_index(cli: &Cli, index: u16, position: u16, doc2_pages: u16, doc2_page_indices: &[u16]) -> u16 {
+    match cli.page2 {
+        Some(page2) => page2 - 1,
+        // The second document is in reverse order, so its last page goes with the first page
+        None if cli.reverse2 => doc2_pages - 1 - index,
+        None => doc2_page_indices[position as usize],
+    }
+}
+
+
+// Whether the differing chunks of a page make more separate areas (boxes) than the 'max-boxes' limit, if there is one
+fn has_too_many_boxes(differing_chunks: &[(u32, u32)], max_boxes: Option<usize>, image_size: (u32, u32)) -> bool {
+    max_boxes.is_some_and(|max_boxes| {
+        // Every area has at least one chunk, so a page with few enough chunks doesn't need them merged
+        differing_chunks.len() > max_boxes && regions::merge_chunks(differing_chunks, images::CHUNK_SIZE, image_size.0, image_size.1).len() > max_boxes
+    })
+}
+
+
+// The 'dump-geometry' line for a page: the size of both pages in points, the size of the rendered page in pixels, and the scales between them
+fn page_geometry_line(cli: &Cli, page: u16, doc1_size: (f32, f32), doc2_size: (f32, f32), image_size: (u32, u32)) -> String {
+    let combined_width = if cli.overlay { image_size.0 } else { image_size.0 * 2 + cli.gutter_width };
+
+    format!("Page {}: {:.2} x {:.2} points (doc1), {:.2} x {:.2} points (doc2), rendered at {} x {} pixels, {:.4} pixels per point, output scale factor {:.4}",
+        page, doc1_size.0, doc1_size.1, doc2_size.0, doc2_size.1, image_size.0, image_size.1,
+        geometry::pixels_per_point(image_size.1, doc1_size.1.round() as f64), geometry::output_scale_factor(combined_width, cli.output_width_inches))
+}
+
+
+// The page (base one) with the lowest fraction of differing chunks, given that fraction for each page of the second document.
+// The first of equally good pages wins; None if there are no pages.
+fn best_matching_page(difference_ratios: &[f64]) -> Option<FoundPage> {
+    let mut found_page: Option<FoundPage> = None;
+
+    for (index, &difference_ratio) in difference_ratios.iter().enumerate() {
+        if found_page.as_ref().is_none_or(|best| difference_ratio < best.difference_ratio) {
+            found_page = Some(FoundPage { page: index as i32 + 1, difference_ratio });
+        }
+    }
+
+    found_page
+}
+
+
+// The passwords of the first and second documents: each its own, or else the one for both
+fn document_passwords(cli: &Cli) -> [Option<&str>; 2] {
+    [cli.password1.as_deref().or(cli.password.as_deref()), cli.password2.as_deref().or(cli.password.as_deref())]
+}
+
+
+// Get a page of a document.  A document can report a page that is too damaged to load, so stop with a clear message if it is.
+fn load_page<'a>(document: &PdfDocument<'a>, index: u16, description: &str) -> Result<PdfPage<'a>, ComparisonError> {
+    document.pages().get(index)
+        .map_err(|e| ComparisonError::corrupt(format!("Page {} of the {} document could not be loaded ({:?}); the file may be corrupt.", index + 1, description, e)))
+}
+
+
+// Check if two files have the same bytes (files of different sizes aren't read).
+// The bytes themselves are compared rather than a hash of them: this can't take two different files for the same,
+// and it stops reading at the first block that differs.
+fn files_are_identical(path1: &Path, path2: &Path) -> std::io::Result<bool> {
+    if std::fs::metadata(path1)?.len() != std::fs::metadata(path2)?.len() {
+        return Ok(false);
+    }
+
+    const BLOCK_SIZE: u64 = 64 * 1024;
+
+    let (mut file1, mut file2) = (File::open(path1)?, File::open(path2)?);
+    let (mut block1, mut block2) = (Vec::new(), Vec::new());
+
+    loop {
+        block1.clear();
+        block2.clear();
+
+        let bytes_read = (&mut file1).take(BLOCK_SIZE).read_to_end(&mut block1)?;
+        (&mut file2).take(BLOCK_SIZE).read_to_end(&mut block2)?;
+
+        if block1 != block2 {
+            return Ok(false);
+        }
+
+        if bytes_read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+
+// A path of '-' stands for standard input
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+
+// Write the result to the result file and append it to the JSON Lines file, if either was asked for
+fn write_results(cli: &Cli, result: &ComparisonResult) -> Result<(), Box<dyn std::error::Error>> {
+    // Describe what was compared, and when, next to the result itself
+    let result_file = ResultFile {
+        schema_version: RESULT_SCHEMA_VERSION,
+        pdf1_path: cli.original_pdf1_path.as_ref().map_or(String::new(), |path| path.display().to_string()),
+        pdf2_path: cli.original_pdf2_path.as_ref().map(|path| path.display().to_string()),
+        timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        result,
+    };
+
+    if let Some(ref path) = cli.result {
+        write_result_file(path, &result_file)?;
+    }
+
+    if let Some(ref path) = cli.result_jsonl {
+        append_result_line(path, &result_file)?;
+    }
+
+    Ok(())
+}
+
+
+// Append the result to a JSON Lines file as a single line, flushed right away so the file can be followed during a long run
+fn append_result_line(path: &Path, result_file: &ResultFile) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "{}", serde_json::to_string(result_file)?)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+
+// Serialize the result and write it to the user-specified file
+fn write_result_file(path: &Path, result_file: &ResultFile) -> Result<(), Box<dyn std::error::Error>> {
+    // Serialize the result
+    let file_content = serde_json::to_string_pretty(result_file).unwrap();
+
+    // Write serialized content to file using the user-specified path
+    let mut file = match File::create(path) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Error creating file: {}", e);
+            return Err(Box::new(std::io::Error::other(
+                "Error creating file",
+            )));
+        }
+    };
+
+    // Write the content to the file, using the file object:
+    if let Err(e) = file.write_all(file_content.as_bytes()) {
+        error!("Error writing to file: {}", e);
+    }
+
+    Ok(())
+}
+
+
+// The shapes to ignore on a page: the shapes of the config file for the page (converted to pixels of the page image),
+// and the bands at the top and bottom of the page
+fn shapes_to_ignore(config: Option<&Config>, bands: &PageBands, page_val: u16, height_in_points: i32, image: &RgbaImage) -> IgnoreShapes {
+    let mut shapes = match config {
+        Some(config) => config.get_matching_shapes(page_val.to_string().as_str(), height_in_points, image.height()),
+        None => Vec::new(),
+    };
+
+    shapes.extend(bands.rectangles(image.width(), image.height()));
+
+    shapes
+}
+
+
+// Compare the images of two pages, ignoring the given shapes.
+// If requested, compare amplified grayscale copies so faint marks stand out (the originals are kept for the output).
+fn compare_page_images(image1: &RgbaImage, image2: &RgbaImage, shapes_to_ignore: &IgnoreShapes, settings: &images::ComparisonSettings) -> Vec<(u32, u32)> {
+    let ignored_shapes_option = if shapes_to_ignore.is_empty() {
+        None
+    } else {
+        Some(shapes_to_ignore)
+    };
+
+    // If the backgrounds differ by a small shift in tone, compare the second image shifted to the background of the first
+    let shifted_image2;
+    let image2 = match settings.normalize_background.then(|| images::background_shift(image1, image2)).flatten() {
+        Some(shift) => {
+            shifted_image2 = images::shift_colors(image2, shift);
+            &shifted_image2
+        },
+        None => image2,
+    };
+
+    if settings.amplify_faint {
+        images::compare_images_in_chunks(&images::amplify_faint(image1), &images::amplify_faint(image2), ignored_shapes_option, settings)
+    } else {
+        images::compare_images_in_chunks(image1, image2, ignored_shapes_option, settings)
+    }
+}
+
+
+// Check that the folder a file will be written to exists, creating it if allowed.  Returns an error if it can't be used.
+// The description is used in messages (e.g. "output" or "result").
+fn check_parent_folder(path: &Path, description: &str, create_dirs: bool) -> Result<(), ComparisonError> {
+    // Extract the parent directory of the provided path
+    match path.parent() {
+        // A bare file name is written to the current folder
+        Some(parent_dir) if parent_dir.as_os_str().is_empty() => check_folder(Path::new("."), description, create_dirs),
+        Some(parent_dir) => check_folder(parent_dir, description, create_dirs),
+        None => Err(ComparisonError::new(format!("Invalid {} path provided.", description))),
+    }
+}
+
+
+// Check that a folder exists and can be written to, creating it if allowed.  Returns an error if it can't be used.
+fn check_folder(folder: &Path, description: &str, create_dirs: bool) -> Result<(), ComparisonError> {
+    // If the folder does not exist, create it or stop now.
+    if ! folder.exists() {
+        if create_dirs {
+            if let Err(e) = std::fs::create_dir_all(folder) {
+                return Err(ComparisonError::new(format!("The provided {} folder could not be created: {}", description, e)));
+            }
+        } else {
+            return Err(ComparisonError::new(format!("The provided {} folder does not exist.", description)));
+        }
+    } else if ! folder.is_dir() {
+        return Err(ComparisonError::new(format!("The provided {} folder is not a folder.", description)));
+    }
+
+    // Check the folder can be written to now, rather than failing after a long comparison:
+    // create a small file in it and remove it again
+    let test_path = folder.join(format!(".match_pdf-write-test-{}", process::id()));
+
+    if let Err(e) = File::create(&test_path) {
+        return Err(ComparisonError::new(format!("The provided {} folder ({}) can't be written to: {}", description, folder.display(), e)));
+    }
+
+    let _ = std::fs::remove_file(&test_path);
+
+    Ok(())
+}
+
+
+// Build the path of one file in a split output series, e.g. differences.pdf -> differences-001.pdf
+fn numbered_output_path(path: &Path, number: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{:03}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{:03}", stem, number),
+    };
+
+    path.with_file_name(file_name)
+}
+
+
+// Check if an output file with this many pages is full, so the next page starts a new file (only when the output is split)
+fn output_file_is_full(pages_in_file: u16, split_output: Option<u16>) -> bool {
+    split_output.is_some_and(|split_pages| pages_in_file >= split_pages)
+}
+
+
+// Why the plan leaves out the page at this index (the flag that skips it), or None if the page is compared
+fn plan_skip_reason(cli: &Cli, index: u16) -> Option<&'static str> {
+    let page_val = index as i32 + 1;
+
+    if cli.from.is_some_and(|from| page_val < from as i32) || cli.to.is_some_and(|to| page_val > to as i32) {
+        Some("outside 'from' and 'to'")
+    } else if cli.maxpages.is_some_and(|max_pages| page_val > max_pages) {
+        Some("beyond 'maxpages'")
+    } else if skipped_by_parity(cli, index) {
+        Some(if cli.odd_only { "'odd-only'" } else { "'even-only'" })
+    } else {
+        None
+    }
+}
+
+
+// Print what a comparison with these flags would do: the pages compared and skipped, the rectangles ignored on each page,
+// and the settings that affect the result
+fn print_comparison_plan(cli: &Cli, config: Option<&Config>, doc1_pages: u16, doc2_pages: u16) {
+    println!("Comparison plan");
+    println!("===============");
+
+    let describe_resolution = |dpi: Option<f32>| match dpi {
+        Some(dpi) => format!("{} dpi", dpi),
+        None => format!("fit within {} x {} pixels", cli.render_width, cli.render_width),
+    };
+
+    println!("Resolution of the first document:  {}", describe_resolution(cli.dpi1));
+    println!("Resolution of the second document:  {}", describe_resolution(cli.dpi2));
+    println!("Neighborhood:  {} pixels", cli.neighborhood);
+    println!("Shift tolerance:  {} pixels", cli.shift_tolerance);
+    println!("Color tolerance:  {}{}", cli.tolerance, if cli.ignore_alpha { " (alpha ignored)" } else { "" });
+
+    if cli.perceptual {
+        println!("Perceptual comparison, mean squared error threshold:  {}", cli.mse_threshold);
+    }
+    println!("Page size tolerance:  {} points", cli.size_epsilon);
+
+    if let Some(threshold) = cli.center_weighted {
+        println!("Center-weighted threshold:  {}", threshold);
+    }
+
+    if let Some(object_type) = cli.object_type {
+        println!("Only comparing objects of type:  {:?}", object_type);
+    }
+
+    if cli.amplify_faint {
+        println!("Faint differences are amplified.");
+    }
+
+    if cli.normalize_background {
+        println!("Slightly different background colors are normalized.");
+    }
+
+    if cli.deskew {
+        println!("Skewed pages are leveled before comparing.");
+    }
+
+    if cli.register {
+        println!("The pages of the second document are lined up with the first before comparing.");
+    }
+
+    if cli.coarse_first {
+        if !cli.justdiff && (cli.output.is_some() || cli.image_dir.is_some() || cli.single_image.is_some()) {
+            println!("Matching pages are written to the output, so every page is rendered at full resolution ('coarse-first' is ignored).");
+        } else {
+            println!("Pages are compared at {} pixels first; pages that match there are not rendered at full resolution.", COARSE_RENDER_WIDTH);
+        }
+    }
+
+    if cli.ignore_blank_pages {
+        println!("Blank pages are left out of both documents (they are found when comparing, so the pages below include them).");
+    }
+
+    if let Some(percent) = cli.ignore_top_percent {
+        println!("The top {}% of every page is ignored.", percent);
+    }
+
+    if let Some(percent) = cli.ignore_bottom_percent {
+        println!("The bottom {}% of every page is ignored.", percent);
+    }
+
+    if let (Some(page1), Some(page2)) = (cli.page1, cli.page2) {
+        println!("Page {} of the first document will be compared with page {} of the second document.", page1, page2);
+
+        if let Some(config) = config {
+            for shape in config.shapes_for_page(&page1.to_string()) {
+                println!("    ignoring {}", shape.describe());
+            }
+        }
+
+        return;
+    }
+
+    if doc1_pages != doc2_pages && cli.reverse2 {
+        println!("The documents have a different number of pages ({} and {}), so the reversed pages can't be compared.", doc1_pages, doc2_pages);
+        return;
+    }
+
+    let common_pages = doc1_pages.min(doc2_pages);
+    let mut compared_pages = 0;
+
+    for index in 0..common_pages {
+        let page_val = index as i32 + 1;
+
+        let skip_reason = plan_skip_reason(cli, index);
+        let doc2_page_val = if cli.reverse2 { (doc2_pages - index) as i32 } else { page_val };
+
+        match skip_reason {
+            Some(reason) => println!("Page {}:  skipped ({})", page_val, reason),
+            None => {
+                compared_pages += 1;
+                println!("Page {}:  compared with page {} of the second document", page_val, doc2_page_val);
+
+                if let Some(config) = config {
+                    for shape in config.shapes_for_page(&page_val.to_string()) {
+                        println!("    ignoring {}", shape.describe());
+                    }
+                }
+            }
+        }
+    }
+
+    // Pages only in the longer document are reported as added or removed (unless the page range ends before them)
+    let extra_pages = if cli.to.is_some() { 0..0 } else { common_pages..doc1_pages.max(doc2_pages) };
+
+    for index in extra_pages {
+        println!("Page {}:  only in the {} document", index + 1, if doc1_pages > doc2_pages { "first" } else { "second" });
+    }
+
+    println!("{} of {} pages will be compared.", compared_pages, common_pages);
+
+    if cli.stop {
+        println!("The comparison will stop after the first page with differences.");
+    }
+
+    if let Some(pages) = cli.pages {
+        println!("The comparison will stop after page {} if differences were found by then.", pages);
+    }
+
+    if let Some(threshold) = cli.fail_threshold {
+        println!("The comparison will only fail if more than {} chunks differ.", threshold);
+    }
+
+    if let Some(percent) = cli.fail_percent {
+        println!("The comparison will only fail if more than {}% of the compared chunks differ.", percent);
+    }
+}
+
+
+// Check if 'odd-only' or 'even-only' leaves out the page with this index (base zero, so odd pages have even indices)
+fn skipped_by_parity(cli: &Cli, index: u16) -> bool {
+    (cli.odd_only && !index.is_multiple_of(2)) || (cli.even_only && index.is_multiple_of(2))
+}
+
+
+// Check the 'from' and 'to' page range against the number of pages that can be compared, None if it's valid
+fn page_range_error(from: Option<u16>, to: Option<u16>, common_pages: u16) -> Option<String> {
+    match (from, to) {
+        (Some(from), Some(to)) if from > to => Some(format!("The 'from' page ({}) is after the 'to' page ({}).", from, to)),
+        (Some(from), _) if from > common_pages => Some(format!("The 'from' page ({}) is beyond the pages both documents have ({}).", from, common_pages)),
+        (_, Some(to)) if to > common_pages => Some(format!("The 'to' page ({}) is beyond the pages both documents have ({}).", to, common_pages)),
+        _ => None,
+    }
+}
+
+
+// Check the 'page1' and 'page2' pages against the number of pages of each document, None if they are valid (or not set)
+fn single_page_error(page1: Option<u16>, page2: Option<u16>, doc1_pages: u16, doc2_pages: u16) -> Option<String> {
+    match (page1, page2) {
+        (Some(page1), _) if page1 > doc1_pages => Some(format!("The 'page1' page ({}) is beyond the pages of the first document ({}).", page1, doc1_pages)),
+        (_, Some(page2)) if page2 > doc2_pages => Some(format!("The 'page2' page ({}) is beyond the pages of the second document ({}).", page2, doc2_pages)),
+        _ => None,
+    }
+}
+
+
+// Check the pages the flags ask for against the number of pages of each document, None if they are all there.
+// In strict mode, a rectangle for a page that doesn't exist is most likely a stale config, so it is an error as well.
+fn page_reference_error(cli: &Cli, config: Option<&Config>, doc1_pages: u16, doc2_pages: u16) -> Option<String> {
+    // A single page of each document can be compared, whatever the number of pages of the documents
+    if let Some(message) = single_page_error(cli.page1, cli.page2, doc1_pages, doc2_pages) {
+        return Some(message);
+    }
+
+    // A page range has to lie within the pages both documents have
+    if let Some(message) = page_range_error(cli.from, cli.to, doc1_pages.min(doc2_pages)) {
+        return Some(message);
+    }
+
+    let invalid_pages = config.filter(|_| cli.strict_config).map(|config| config.invalid_page_references(doc1_pages)).unwrap_or_default();
+
+    if !invalid_pages.is_empty() {
+        return Some(format!("The config file has rectangles for pages that are not in the documents ({} pages): {}", doc1_pages, invalid_pages.join(", ")));
+    }
+
+    None
+}
+
+
+// The page (base one) of the first difference: the first page that differed, or else the first page that only exists in the longer document.
+// None if the documents match.
+fn first_difference_page(differing_pages: &[i32], first_extra_page: Option<i32>) -> Option<i32> {
+    differing_pages.first().copied().or(first_extra_page)
+}
+
+
+// Collapse an ordered list of page numbers into ranges of consecutive pages, e.g. 5, 6, 7, 9 becomes "5-7, 9"
+fn format_page_ranges(pages: &[i32]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut index = 0;
+
+    while index < pages.len() {
+        let first = pages[index];
+        let mut last = first;
+
+        // Extend the range while the next page follows on
+        while index + 1 < pages.len() && pages[index + 1] == last + 1 {
+            index += 1;
+            last = pages[index];
+        }
+
+        if first == last {
+            ranges.push(first.to_string());
+        } else {
+            ranges.push(format!("{}-{}", first, last));
+        }
+
+        index += 1;
+    }
+
+    ranges.join(", ")
+}
+
+
+// Find the indices of the pages of the document that aren't blank (see images::is_blank), rendering each page
+fn non_blank_pages(document: &PdfDocument, render_config: &PdfRenderConfig, description: &str) -> Result<Vec<u16>, Box<dyn std::error::Error>> {
+    let mut page_indices = Vec::new();
+
+    for index in 0..document.pages().len() {
+        if !images::is_blank(&images::render_page(&load_page(document, index, description)?, render_config)?) {
+            page_indices.push(index);
+        }
+    }
+
+    Ok(page_indices)
+}
+
+
+// Build the pdf to image rendering options, either at the standard size or at a specific DPI (72 points per inch)
+// (the standard size fits the page within 'render-width' pixels in both directions)
+fn build_render_config(dpi: Option<f32>, cli: &Cli) -> PdfRenderConfig {
+    let render_config = match dpi {
+        Some(dpi) => PdfRenderConfig::new()
+            .scale_page_by_factor(dpi / geometry::POINTS_PER_INCH as f32),
+        None => PdfRenderConfig::new()
+            .set_target_width(cli.render_width as i32)
+            .set_maximum_height(cli.render_width as i32),
+    };
+
+    // Both documents are rendered with the same content, so the comparison covers the same things
+    render_config
+        .render_form_data(cli.render.includes_form_data())
+        .render_annotations(cli.render.includes_annotations())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_ranges_of_no_pages() {
+        assert_eq!(format_page_ranges(&[]), "");
+    }
+
+    #[test]
+    fn page_ranges_of_a_single_page() {
+        assert_eq!(format_page_ranges(&[4]), "4");
+    }
+
+    #[test]
+    fn page_ranges_of_consecutive_pages() {
+        assert_eq!(format_page_ranges(&[5, 6, 7, 8, 9, 10, 11, 12]), "5-12");
+    }
+
+    #[test]
+    fn page_ranges_of_mixed_pages() {
+        assert_eq!(format_page_ranges(&[5, 6, 7, 9]), "5-7, 9");
+        assert_eq!(format_page_ranges(&[1, 3, 4, 20, 25, 26, 27]), "1, 3-4, 20, 25-27");
+    }
+
+    #[test]
+    fn histogram_counts_pages_by_differing_chunks() {
+        let mut histogram = DifferenceHistogram::default();
+
+        for differing_chunks in [0, 0, 1, 10, 11, 100, 101, 5000, 0] {
+            histogram.add_page(differing_chunks);
+        }
+
+        assert_eq!((histogram.no_differences, histogram.up_to_10, histogram.up_to_100, histogram.over_100), (3, 2, 2, 2));
+    }
+
+    #[test]
+    fn histogram_buckets_are_named_by_their_ranges() {
+        let mut histogram = DifferenceHistogram::default();
+        histogram.add_page(3);
+
+        assert_eq!(serde_json::to_string(&histogram).unwrap(), r#"{"0":0,"1-10":1,"11-100":0,"101+":0}"#);
+    }
+
+    #[test]
+    fn resample_filter_changes_the_differing_chunks() {
+        // A 100 x 100 page with a black square, and the same page rendered at half the size
+        let square_page = |size: u32, from: u32, to: u32| RgbaImage::from_fn(size, size, |x, y| {
+            if (from..to).contains(&x) && (from..to).contains(&y) { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255, 255, 255, 255]) }
+        });
+        let settings = images::ComparisonSettings::default();
+
+        let differing_chunks = |filter: ResampleFilter| {
+            let (image1, image2) = images::match_image_sizes(square_page(100, 20, 60), square_page(50, 10, 30), filter.filter_type());
+
+            compare_page_images(&image1, &image2, &Vec::new(), &settings).len()
+        };
+
+        // Nearest keeps the edges of the square sharp, so the enlarged page is the same as the full size one;
+        // the smoothing filters blur the edges into gray, so the chunks on the edges differ
+        let nearest = differing_chunks(ResampleFilter::Nearest);
+        let triangle = differing_chunks(ResampleFilter::Triangle);
+        let lanczos = differing_chunks(ResampleFilter::Lanczos);
+
+        assert_eq!(nearest, 0);
+        assert!(triangle > 0);
+        assert!(lanczos > 0);
+    }
+
+    #[test]
+    fn split_output_files_are_numbered() {
+        assert_eq!(numbered_output_path(Path::new("out/differences.pdf"), 1), PathBuf::from("out/differences-001.pdf"));
+        assert_eq!(numbered_output_path(Path::new("differences.pdf"), 12), PathBuf::from("differences-012.pdf"));
+        assert_eq!(numbered_output_path(Path::new("out/differences"), 3), PathBuf::from("out/differences-003"));
+    }
+
+    #[test]
+    fn split_output_files_hold_at_most_the_pages_asked_for() {
+        // Add 5 pages as add_output_page does, starting a new file whenever the current one is full
+        let mut pages_in_files: Vec<u16> = vec![0];
+
+        for _ in 0..5 {
+            if output_file_is_full(*pages_in_files.last().unwrap(), Some(2)) {
+                pages_in_files.push(0);
+            }
+
+            *pages_in_files.last_mut().unwrap() += 1;
+        }
+
+        assert_eq!(pages_in_files, vec![2, 2, 1]);
+
+        // Without 'split-output', a file is never full
+        assert!(!output_file_is_full(1000, None));
+    }
+
+    #[test]
+    fn pages_rendered_at_different_dpi_are_compared_on_one_grid() {
+        // The same page with a black bar, rendered at 72 DPI (1 inch = 72 pixels) and at 144 DPI
+        let bar_page = |pixels_per_inch: u32| RgbaImage::from_fn(4 * pixels_per_inch, 2 * pixels_per_inch, |_, y| {
+            if (pixels_per_inch..pixels_per_inch + pixels_per_inch / 2).contains(&y) { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255, 255, 255, 255]) }
+        });
+
+        let (image1, image2) = images::match_image_sizes(bar_page(72), bar_page(144), ResampleFilter::Triangle.filter_type());
+
+        // The first page is enlarged to the grid of the second, and the bar lines up: only the chunks on its blurred edges can differ
+        assert_eq!(image1.dimensions(), (576, 288));
+        assert_eq!(image2.dimensions(), (576, 288));
+
+        let differing_rows: Vec<u32> = compare_page_images(&image1, &image2, &Vec::new(), &images::ComparisonSettings::default())
+            .into_iter().map(|(_, y)| y).collect();
+
+        assert!(differing_rows.iter().all(|&y| y == 140 || y == 210), "{:?}", differing_rows);
+    }
+
+    #[test]
+    fn missing_output_folders_are_created_with_create_dirs() {
+        let root = std::env::temp_dir().join(format!("match_pdf-create-dirs-{}", std::process::id()));
+        let output_path = root.join("nested").join("deeper").join("differences.pdf");
+        let _ = std::fs::remove_dir_all(&root);
+
+        // Without 'create-dirs', a missing folder stops the comparison before anything is done
+        assert!(check_parent_folder(&output_path, "output", false).is_err());
+        assert!(!root.exists());
+
+        // With it, the whole path is created, and a file can be written there
+        check_parent_folder(&output_path, "output", true).unwrap();
+        std::fs::write(&output_path, b"output").unwrap();
+
+        assert!(output_path.exists());
+
+        // The same goes for the image folders, which are checked themselves rather than their parent
+        let image_dir = root.join("images");
+        check_folder(&image_dir, "image", true).unwrap();
+
+        assert!(image_dir.is_dir());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn first_difference_page_is_the_earliest_differing_page() {
+        // Pages 4 and 7 differ, and the second document has an extra page 11
+        assert_eq!(first_difference_page(&[4, 7], Some(11)), Some(4));
+
+        // Only the number of pages differs
+        assert_eq!(first_difference_page(&[], Some(11)), Some(11));
+    }
+
+    #[test]
+    fn first_difference_page_is_null_for_matching_documents() {
+        assert_eq!(first_difference_page(&[], None), None);
+
+        let result = ComparisonResult { match_result: "Documents match".to_string(), ..Default::default() };
+        let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+
+        assert!(json["first_difference_page"].is_null());
+    }
+
+    #[test]
+    fn odd_only_compares_the_odd_pages() {
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--odd-only"]);
+
+        // Pages 2 and 4 differ, but only pages 1, 3 and 5 are compared
+        let differing_pages = [2, 4];
+        let compared_pages: Vec<u16> = (0..6).filter(|&index| !skipped_by_parity(&cli, index)).map(|index| index + 1).collect();
+
+        assert_eq!(compared_pages, vec![1, 3, 5]);
+        assert!(compared_pages.iter().all(|page| !differing_pages.contains(page)));
+    }
+
+    #[test]
+    fn even_only_compares_the_even_pages() {
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--even-only"]);
+        let compared_pages: Vec<u16> = (0..6).filter(|&index| !skipped_by_parity(&cli, index)).map(|index| index + 1).collect();
+
+        assert_eq!(compared_pages, vec![2, 4, 6]);
+
+        // Without either flag, every page is compared
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf"]);
+
+        assert!((0..6).all(|index| !skipped_by_parity(&cli, index)));
+    }
+
+    // A 30 x 20 page at the standard resolution, and at three times that (the confirm DPI), with single pixel specks
+    // (at the standard resolution) at the given positions
+    fn speckled_pages(specks: &[(u32, u32)], scale: u32) -> RgbaImage {
+        RgbaImage::from_fn(30 * scale, 20 * scale, |x, y| {
+            if specks.contains(&(x / scale, y / scale)) { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255, 255, 255, 255]) }
+        })
+    }
+
+    #[test]
+    fn small_differences_are_confirmed_or_dismissed_at_a_higher_resolution() {
+        let settings = images::ComparisonSettings::default();
+        let blank = speckled_pages(&[], 1);
+
+        // At the standard resolution, the second page has two specks: rendering noise at (3, 3), and a real mark at (25, 15)
+        let standard_chunks = compare_page_images(&blank, &speckled_pages(&[(3, 3), (25, 15)], 1), &Vec::new(), &settings);
+
+        assert_eq!(standard_chunks, vec![(0, 0), (20, 10)]);
+
+        // At the confirm resolution the noise is gone but the mark persists: only its chunk is confirmed
+        let confirm_chunks = compare_page_images(&speckled_pages(&[], 3), &speckled_pages(&[(25, 15)], 3), &Vec::new(), &settings);
+
+        assert_eq!(images::scale_chunks(&confirm_chunks, (90, 60), blank.dimensions()), vec![(20, 10)]);
+
+        // Noise only: nothing is confirmed, so the page is treated as matching
+        let confirm_chunks = compare_page_images(&speckled_pages(&[], 3), &speckled_pages(&[], 3), &Vec::new(), &settings);
+
+        assert!(images::scale_chunks(&confirm_chunks, (90, 60), blank.dimensions()).is_empty());
+    }
+
+    #[test]
+    fn each_document_gets_its_own_password() {
+        let cli = Cli::parse_from(["match_pdf", "signed.pdf", "draft.pdf", "--password1", "first", "--password2", "second"]);
+        assert_eq!(document_passwords(&cli), [Some("first"), Some("second")]);
+
+        // The password for both documents only fills in for a document without its own
+        let cli = Cli::parse_from(["match_pdf", "signed.pdf", "draft.pdf", "--password", "both", "--password2", "second"]);
+        assert_eq!(document_passwords(&cli), [Some("both"), Some("second")]);
+
+        let cli = Cli::parse_from(["match_pdf", "signed.pdf", "draft.pdf"]);
+        assert_eq!(document_passwords(&cli), [None, None]);
+    }
+
+    #[test]
+    fn load_errors_name_the_document_that_failed() {
+        let password_error = || PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError);
+
+        // A wrong password, and a missing one, say which document it is and which flag to use
+        let error = document_load_error(password_error(), true, Path::new("draft.pdf"), "second", "--password2 or --password");
+        assert_eq!(error.to_string(), "The password for the second document (draft.pdf) is not correct.");
+        assert_eq!(error_exit_code(error.as_ref()), ERROR_EXIT_CODE);
+
+        let error = document_load_error(password_error(), false, Path::new("signed.pdf"), "first", "--password1 or --password");
+        assert_eq!(error.to_string(), "The first document (signed.pdf) is password protected; supply its password with --password1 or --password.");
+
+        // A document that is damaged in any other way is corrupt
+        let error = document_load_error(PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::FormatError), false, Path::new("signed.pdf"), "first", "--password1 or --password");
+        assert_eq!(error_exit_code(error.as_ref()), CORRUPT_PDF_EXIT_CODE);
+    }
+
+    #[test]
+    fn dump_geometry_prints_points_and_pixels() {
+        // A US Letter page (612 x 792 points) rendered at 2 pixels per point
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--dump-geometry"]);
+        let line = page_geometry_line(&cli, 3, (612.0, 792.0), (595.0, 842.0), (1224, 1584));
+
+        assert!(line.starts_with("Page 3: 612.00 x 792.00 points (doc1), 595.00 x 842.00 points (doc2), rendered at 1224 x 1584 pixels, 2.0000 pixels per point"), "{}", line);
+        let side_by_side = geometry::output_scale_factor(1224 * 2 + cli.gutter_width, cli.output_width_inches);
+        assert!(line.ends_with(&format!("output scale factor {:.4}", side_by_side)), "{}", line);
+
+        // An overlay is only one page wide
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--dump-geometry", "--overlay"]);
+        let line = page_geometry_line(&cli, 3, (612.0, 792.0), (612.0, 792.0), (1224, 1584));
+        assert!(line.ends_with(&format!("output scale factor {:.4}", geometry::output_scale_factor(1224, cli.output_width_inches))), "{}", line);
+    }
+
+    #[test]
+    fn pages_with_more_areas_than_max_boxes_are_marked_as_heavily_changed() {
+        // Three separate areas, one of them two chunks wide
+        let chunks = [(0, 0), (10, 0), (50, 50), (100, 100)];
+
+        assert!(has_too_many_boxes(&chunks, Some(2), (200, 200)));
+        assert!(!has_too_many_boxes(&chunks, Some(3), (200, 200)));
+        assert!(!has_too_many_boxes(&chunks, None, (200, 200)));
+    }
+
+    #[test]
+    fn reversed_second_document_lines_up_with_the_first() {
+        // The pages of the first document, and a copy of it in reverse order
+        let doc1 = ["cover", "contents", "chapter", "index"];
+        let doc2 = ["index", "chapter", "contents", "cover"];
+        let doc2_page_indices: Vec<u16> = (0..4).collect();
+
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--reverse2"]);
+        for index in 0..4u16 {
+            assert_eq!(doc2[second_page_index(&cli, index, index, 4, &doc2_page_indices) as usize], doc1[index as usize]);
+        }
+
+        // Without the flag, the pages are compared in the same order
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf"]);
+        assert_eq!(second_page_index(&cli, 0, 0, 4, &doc2_page_indices), 0);
+        assert_eq!(second_page_index(&cli, 3, 3, 4, &doc2_page_indices), 3);
+    }
+
+    #[test]
+    fn plan_shows_which_pages_are_compared_and_why_the_others_are_skipped() {
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf"]);
+        assert!((0..10).all(|index| plan_skip_reason(&cli, index).is_none()));
+
+        // Pages 3 to 6, of which only the odd ones
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--from", "3", "--to", "6", "--odd-only"]);
+        let reasons: Vec<Option<&str>> = (0..8).map(|index| plan_skip_reason(&cli, index)).collect();
+        assert_eq!(reasons, vec![
+            Some("outside 'from' and 'to'"), Some("outside 'from' and 'to'"), None, Some("'odd-only'"),
+            None, Some("'odd-only'"), Some("outside 'from' and 'to'"), Some("outside 'from' and 'to'"),
+        ]);
+
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--maxpages", "2", "--even-only"]);
+        assert_eq!(plan_skip_reason(&cli, 0), Some("'even-only'"));
+        assert_eq!(plan_skip_reason(&cli, 1), None);
+        assert_eq!(plan_skip_reason(&cli, 3), Some("beyond 'maxpages'"));
+    }
+
+    #[test]
+    fn page_lists_are_parsed_into_ranges() {
+        assert_eq!(parse_page_list("3"), Some(vec![(3, 3)]));
+        assert_eq!(parse_page_list("3-7"), Some(vec![(3, 7)]));
+        assert_eq!(parse_page_list("1, 4,9-12"), Some(vec![(1, 1), (4, 4), (9, 12)]));
+
+        // Backward ranges, words and empty parts are malformed
+        assert_eq!(parse_page_list("7-3"), None);
+        assert_eq!(parse_page_list("first"), None);
+        assert_eq!(parse_page_list("1,,4"), None);
+        assert_eq!(parse_page_list(""), None);
+    }
+
+    #[test]
+    fn shapes_apply_to_the_pages_they_name() {
+        assert!(page_matches("all", "5"));
+        assert!(page_matches("even", "4") && !page_matches("even", "5"));
+        assert!(page_matches("odd", "5") && !page_matches("odd", "4"));
+        assert!(page_matches("1,4,9-12", "10") && !page_matches("1,4,9-12", "5"));
+        assert!(!page_matches("7-3", "5"));
+    }
+
+    #[test]
+    fn rectangle_for_a_missing_page_is_an_error_in_strict_mode() {
+        let config: Config = serde_json::from_str(r#"{"ignored_rectangles": [
+            {"page": "all", "top_left": [0.0, 0.0], "bottom_right": [1.0, 1.0]},
+            {"page": "12", "top_left": [0.0, 0.0], "bottom_right": [1.0, 1.0]}
+        ]}"#).unwrap();
+
+        assert_eq!(config.invalid_page_references(10), vec!["'12'".to_string()]);
+        assert!(config.invalid_page_references(12).is_empty());
+
+        // Only strict mode turns the stale rectangle into an error
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--strict-config"]);
+        assert_eq!(page_reference_error(&cli, Some(&config), 10, 10),
+            Some("The config file has rectangles for pages that are not in the documents (10 pages): '12'".to_string()));
+
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf"]);
+        assert_eq!(page_reference_error(&cli, Some(&config), 10, 10), None);
+    }
+
+    #[test]
+    fn find_page_picks_the_page_with_the_fewest_differences() {
+        let found = best_matching_page(&[0.4, 0.05, 0.3, 0.05]).unwrap();
+
+        // The first of the two best pages, numbered from one
+        assert_eq!(found.page, 2);
+        assert_eq!(found.difference_ratio, 0.05);
+
+        assert_eq!(best_matching_page(&[0.2, 0.0]).unwrap().page, 2);
+        assert!(best_matching_page(&[]).is_none());
+    }
+
+    #[test]
+    fn form_fields_and_annotations_are_only_rendered_when_asked_for() {
+        let included = |render: RenderContent| (render.includes_form_data(), render.includes_annotations());
+
+        assert_eq!(included(RenderContent::Content), (false, false));
+        assert_eq!(included(RenderContent::Forms), (true, false));
+        assert_eq!(included(RenderContent::Annotations), (false, true));
+        assert_eq!(included(RenderContent::All), (true, true));
+
+        // Annotations, but not the values of form fields, unless the flag says otherwise
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf"]);
+        assert_eq!(included(cli.render), (false, true));
+
+        let cli = Cli::parse_from(["match_pdf", "first.pdf", "second.pdf", "--render", "forms"]);
+        assert_eq!(included(cli.render), (true, false));
+    }
+
+    #[test]
+    fn identical_files_are_found_by_their_bytes() {
+        let folder = std::env::temp_dir().join(format!("match_pdf-identical-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+
+        // Larger than one block, with the only difference in the last block
+        let bytes: Vec<u8> = (0..200_000u32).map(|value| (value % 251) as u8).collect();
+        let mut changed = bytes.clone();
+        *changed.last_mut().unwrap() ^= 1;
+
+        let write = |name: &str, content: &[u8]| {
+            let path = folder.join(name);
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        let (original, copy, changed, shorter) = (write("original", &bytes), write("copy", &bytes), write("changed", &changed), write("shorter", &bytes[..1000]));
+
+        assert!(files_are_identical(&original, &copy).unwrap());
+        assert!(!files_are_identical(&original, &changed).unwrap());
+        assert!(!files_are_identical(&original, &shorter).unwrap());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
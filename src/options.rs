@@ -0,0 +1,344 @@
+// Options related functions (the options of a comparison run from another program, without the command line)
+
+use std::path::PathBuf;
+use image::Rgba;
+use crate::{geometry, Cli, HighlightPalette, ObjectType, RenderContent, ResampleFilter};
+use crate::geometry::{MAX_PAGE_SIZE_IN_POINTS, POINTS_PER_INCH};
+
+
+// The options of a comparison, for use from other programs.  Each field is the flag of the same name (see the README);
+// Options::default() has the default value of each.  The flags that only make sense on the command line
+// (e.g. 'debug', 'quiet', 'batch' or 'threads') are left out: the calling program sets up its own logger and thread pool.
+#[derive(Debug, Clone)]
+pub struct Options {
+    // Which pages are compared, and when the comparison stops
+    pub stop: bool,
+    pub justdiff: bool,
+    pub pages: Option<i32>,
+    pub maxpages: Option<i32>,
+    pub from: Option<u16>,
+    pub to: Option<u16>,
+    pub page1: Option<u16>,
+    pub page2: Option<u16>,
+    pub odd_only: bool,
+    pub even_only: bool,
+    pub reverse2: bool,
+    pub ignore_blank_pages: bool,
+
+    // The files and folders that are written (only if their paths are set)
+    pub output: Option<PathBuf>,
+    pub result: Option<PathBuf>,
+    pub result_jsonl: Option<PathBuf>,
+    pub split_output: Option<u16>,
+    pub gif_dir: Option<PathBuf>,
+    pub gif_delay: u32,
+    pub image_dir: Option<PathBuf>,
+    pub single_image: Option<PathBuf>,
+    pub render_cache: Option<PathBuf>,
+    pub create_dirs: bool,
+
+    // The files that are read
+    pub config: Option<PathBuf>,
+    pub acknowledge: Option<PathBuf>,
+    pub strict_config: bool,
+    pub password1: Option<String>,
+    pub password2: Option<String>,
+    pub password: Option<String>,
+    pub pdfium_path: Option<PathBuf>,
+
+    // How the pages are rendered
+    pub render: RenderContent,
+    pub render_width: u16,
+    pub dpi1: Option<f32>,
+    pub dpi2: Option<f32>,
+    pub resample: ResampleFilter,
+    pub object_type: Option<ObjectType>,
+    pub ignore_rotation: bool,
+    pub deskew: bool,
+    pub register: bool,
+    pub coarse_first: bool,
+
+    // How strictly the pages are compared
+    pub tolerance: u8,
+    pub neighborhood: u32,
+    pub shift_tolerance: u32,
+    pub size_epsilon: f32,
+    pub ignore_alpha: bool,
+    pub amplify_faint: bool,
+    pub normalize_background: bool,
+    pub perceptual: bool,
+    pub mse_threshold: f64,
+    pub center_weighted: Option<f64>,
+    pub ssim_threshold: Option<f64>,
+    pub confirm_small: Option<u32>,
+    pub confirm_dpi: f32,
+    pub ignore_top_percent: Option<f64>,
+    pub ignore_bottom_percent: Option<f64>,
+    pub fail_threshold: Option<usize>,
+    pub fail_percent: Option<f64>,
+
+    // What else is compared and reported
+    pub detect_moves: bool,
+    pub max_boxes: Option<usize>,
+    pub form_fields: bool,
+    pub text: bool,
+
+    // How the output looks
+    pub palette: HighlightPalette,
+    pub highlight_color: Option<Rgba<u8>>,
+    pub border_thickness: u32,
+    pub overlay: bool,
+    pub gutter_width: u32,
+    pub separator_color: Rgba<u8>,
+    pub output_width_inches: f64,
+}
+
+
+impl Default for Options {
+
+    // The same defaults as the command line
+    fn default() -> Self {
+        Options {
+            stop: false,
+            justdiff: false,
+            pages: None,
+            maxpages: None,
+            from: None,
+            to: None,
+            page1: None,
+            page2: None,
+            odd_only: false,
+            even_only: false,
+            reverse2: false,
+            ignore_blank_pages: false,
+            output: None,
+            result: None,
+            result_jsonl: None,
+            split_output: None,
+            gif_dir: None,
+            gif_delay: 500,
+            image_dir: None,
+            single_image: None,
+            render_cache: None,
+            create_dirs: false,
+            config: None,
+            acknowledge: None,
+            strict_config: false,
+            password1: None,
+            password2: None,
+            password: None,
+            pdfium_path: None,
+            render: RenderContent::Annotations,
+            render_width: 2000,
+            dpi1: None,
+            dpi2: None,
+            resample: ResampleFilter::Triangle,
+            object_type: None,
+            ignore_rotation: false,
+            deskew: false,
+            register: false,
+            coarse_first: false,
+            tolerance: 0,
+            neighborhood: 0,
+            shift_tolerance: 0,
+            size_epsilon: 0.5,
+            ignore_alpha: false,
+            amplify_faint: false,
+            normalize_background: false,
+            perceptual: false,
+            mse_threshold: 10.0,
+            center_weighted: None,
+            ssim_threshold: None,
+            confirm_small: None,
+            confirm_dpi: 300.0,
+            ignore_top_percent: None,
+            ignore_bottom_percent: None,
+            fail_threshold: None,
+            fail_percent: None,
+            detect_moves: false,
+            max_boxes: None,
+            form_fields: false,
+            text: false,
+            palette: HighlightPalette::Default,
+            highlight_color: None,
+            border_thickness: 3,
+            overlay: false,
+            gutter_width: 5,
+            separator_color: Rgba([0, 0, 0, 255]),
+            output_width_inches: geometry::DEFAULT_OUTPUT_WIDTH_IN_INCHES,
+        }
+    }
+}
+
+
+impl Options {
+
+    // Check the ranges and combinations of the options that the command line checks for its flags, None if they are valid.
+    // Options are set in code, so without this check a page 0 or a border of 0 pixels would reach the comparison.
+    pub(crate) fn option_error(&self) -> Option<String> {
+        let band_is_invalid = |percent: Option<f64>| percent.is_some_and(|percent| !(percent > 0.0 && percent < 100.0));
+        let dpi_is_invalid = |dpi: Option<f32>| dpi.is_some_and(|dpi| !(dpi > 0.0 && dpi.is_finite()));
+        let max_output_width = MAX_PAGE_SIZE_IN_POINTS / POINTS_PER_INCH;
+
+        let checks = [
+            (self.from == Some(0), "The 'from' page has to be at least 1.".to_string()),
+            (self.to == Some(0), "The 'to' page has to be at least 1.".to_string()),
+            (self.page1 == Some(0) || self.page2 == Some(0), "The 'page1' and 'page2' pages have to be at least 1.".to_string()),
+            (self.page1.is_some() != self.page2.is_some(), "The 'page1' and 'page2' options have to be used together.".to_string()),
+            (self.page1.is_some() && (self.from.is_some() || self.to.is_some() || self.odd_only || self.even_only || self.reverse2),
+                "The 'page1' option can't be used with 'from', 'to', 'odd-only', 'even-only' or 'reverse2'.".to_string()),
+            (self.odd_only && self.even_only, "The 'odd-only' and 'even-only' options can't be used together.".to_string()),
+            (self.ignore_blank_pages && (self.page1.is_some() || self.reverse2 || self.from.is_some() || self.to.is_some()),
+                "The 'ignore-blank-pages' option can't be used with 'page1', 'page2', 'reverse2', 'from' or 'to'.".to_string()),
+            (self.fail_threshold.is_some() && self.fail_percent.is_some(), "The 'fail-threshold' and 'fail-percent' options can't be used together.".to_string()),
+            (self.split_output == Some(0), "The 'split-output' pages per file have to be at least 1.".to_string()),
+            (dpi_is_invalid(self.dpi1) || dpi_is_invalid(self.dpi2), "The 'dpi1' and 'dpi2' resolutions have to be more than 0 DPI.".to_string()),
+            (dpi_is_invalid(Some(self.confirm_dpi)), "The 'confirm-dpi' resolution has to be more than 0 DPI.".to_string()),
+            (!(self.size_epsilon >= 0.0 && self.size_epsilon.is_finite()), format!("The page size tolerance ({}) has to be 0 points or more.", self.size_epsilon)),
+            (self.render_width < 100, format!("The render width ({}) has to be at least 100 pixels.", self.render_width)),
+            (!(1..=50).contains(&self.border_thickness), format!("The border thickness ({}) has to be between 1 and 50 pixels.", self.border_thickness)),
+            (!(1..=100).contains(&self.gutter_width), format!("The gutter width ({}) has to be between 1 and 100 pixels.", self.gutter_width)),
+            (!(1.0..=max_output_width).contains(&self.output_width_inches),
+                format!("The output width ({}) has to be between 1 and {} inches.", self.output_width_inches, max_output_width)),
+            (band_is_invalid(self.ignore_top_percent) || band_is_invalid(self.ignore_bottom_percent),
+                "The 'ignore-top-percent' and 'ignore-bottom-percent' bands have to be more than 0 and less than 100 percent.".to_string()),
+        ];
+
+        checks.into_iter().find(|(invalid, _)| *invalid).map(|(_, message)| message)
+    }
+
+    // The command line with these options, without the document paths and with the defaults of the command line only flags.
+    // As no flag is given on a command line, the settings of a config file take the place of the options they cover.
+    pub(crate) fn into_cli(self) -> Cli {
+        Cli {
+            original_pdf1_path: None,
+            original_pdf2_path: None,
+            debug: false,
+            quiet: false,
+            plan: false,
+            dump_geometry: false,
+            pick_regions: None,
+            pick_page: 1,
+            pick_region: Vec::new(),
+            validate_config: None,
+            find_page: None,
+            baseline_dir: false,
+            batch: false,
+            threads: None,
+            expect_diff: false,
+            command_line_flags: Vec::new(),
+            stop: self.stop,
+            justdiff: self.justdiff,
+            pages: self.pages,
+            maxpages: self.maxpages,
+            from: self.from,
+            to: self.to,
+            page1: self.page1,
+            page2: self.page2,
+            odd_only: self.odd_only,
+            even_only: self.even_only,
+            reverse2: self.reverse2,
+            ignore_blank_pages: self.ignore_blank_pages,
+            output: self.output,
+            result: self.result,
+            result_jsonl: self.result_jsonl,
+            split_output: self.split_output,
+            gif_dir: self.gif_dir,
+            gif_delay: self.gif_delay,
+            image_dir: self.image_dir,
+            single_image: self.single_image,
+            render_cache: self.render_cache,
+            create_dirs: self.create_dirs,
+            config: self.config,
+            acknowledge: self.acknowledge,
+            strict_config: self.strict_config,
+            password1: self.password1,
+            password2: self.password2,
+            password: self.password,
+            pdfium_path: self.pdfium_path,
+            render: self.render,
+            render_width: self.render_width,
+            dpi1: self.dpi1,
+            dpi2: self.dpi2,
+            resample: self.resample,
+            object_type: self.object_type,
+            ignore_rotation: self.ignore_rotation,
+            deskew: self.deskew,
+            register: self.register,
+            coarse_first: self.coarse_first,
+            tolerance: self.tolerance,
+            neighborhood: self.neighborhood,
+            shift_tolerance: self.shift_tolerance,
+            size_epsilon: self.size_epsilon,
+            ignore_alpha: self.ignore_alpha,
+            amplify_faint: self.amplify_faint,
+            normalize_background: self.normalize_background,
+            perceptual: self.perceptual,
+            mse_threshold: self.mse_threshold,
+            center_weighted: self.center_weighted,
+            ssim_threshold: self.ssim_threshold,
+            confirm_small: self.confirm_small,
+            confirm_dpi: self.confirm_dpi,
+            ignore_top_percent: self.ignore_top_percent,
+            ignore_bottom_percent: self.ignore_bottom_percent,
+            fail_threshold: self.fail_threshold,
+            fail_percent: self.fail_percent,
+            detect_moves: self.detect_moves,
+            max_boxes: self.max_boxes,
+            form_fields: self.form_fields,
+            text: self.text,
+            palette: self.palette,
+            highlight_color: self.highlight_color,
+            border_thickness: self.border_thickness,
+            overlay: self.overlay,
+            gutter_width: self.gutter_width,
+            separator_color: self.separator_color,
+            output_width_inches: self.output_width_inches,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compare_pdfs, error_exit_code, ERROR_EXIT_CODE};
+    use std::path::Path;
+
+    #[test]
+    fn default_options_are_valid() {
+        assert_eq!(Options::default().option_error(), None);
+    }
+
+    #[test]
+    fn out_of_range_options_are_errors() {
+        let invalid_options = [
+            Options { from: Some(0), ..Options::default() },
+            Options { page1: Some(0), page2: Some(1), ..Options::default() },
+            Options { page1: Some(2), ..Options::default() },
+            Options { border_thickness: 0, ..Options::default() },
+            Options { split_output: Some(0), ..Options::default() },
+            Options { odd_only: true, even_only: true, ..Options::default() },
+            Options { dpi2: Some(0.0), ..Options::default() },
+            Options { confirm_dpi: -300.0, ..Options::default() },
+            Options { size_epsilon: -0.1, ..Options::default() },
+            Options { render_width: 99, ..Options::default() },
+            Options { gutter_width: 101, ..Options::default() },
+            Options { output_width_inches: 0.5, ..Options::default() },
+            Options { ignore_top_percent: Some(100.0), ..Options::default() },
+        ];
+
+        for options in invalid_options {
+            let expected_message = options.option_error().unwrap_or_else(|| panic!("{:?} is not found invalid", options));
+
+            // The options are checked before the documents are read (these don't exist), so the error is about the options
+            match compare_pdfs(Path::new("missing1.pdf"), Path::new("missing2.pdf"), options) {
+                Ok(_) => panic!("invalid options were compared: {}", expected_message),
+                Err(error) => {
+                    assert_eq!(error.to_string(), expected_message);
+                    assert_eq!(error_exit_code(error.as_ref()), ERROR_EXIT_CODE);
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Region picker related functions (help authoring exclusion rectangles)
+
+use image::{Rgba, RgbaImage};
+use crate::geometry;
+use crate::{Rectangle, RectangleUnit};
+
+
+// Draw a grid on the image: a line every half inch, with darker lines every inch,
+// so coordinates can be read off the page image
+pub fn draw_inch_grid(image: &RgbaImage, pixels_per_inch: f64) -> RgbaImage {
+    let mut new_image = image.clone();
+    let (width, height) = new_image.dimensions();
+
+    let inch_color = Rgba([0, 120, 255, 255]);
+    let half_inch_color = Rgba([160, 200, 255, 255]);
+
+    let mut half_inches = 0;
+
+    loop {
+        let position = (half_inches as f64 * pixels_per_inch / 2.0).round() as u32;
+
+        if position >= width && position >= height {
+            break;
+        }
+
+        let color = if half_inches % 2 == 0 { inch_color } else { half_inch_color };
+
+        // Vertical line
+        if position < width {
+            for y in 0..height {
+                new_image.put_pixel(position, y, color);
+            }
+        }
+
+        // Horizontal line
+        if position < height {
+            for x in 0..width {
+                new_image.put_pixel(x, position, color);
+            }
+        }
+
+        half_inches += 1;
+    }
+
+    new_image
+}
+
+
+// Convert a rectangle in pixels of the page image (x1, y1, x2, y2) to an exclusion rectangle in inches
+pub fn pixels_to_inch_rectangle(page: &str, pixel_region: [f64; 4], pixels_per_point: f64) -> Rectangle {
+    // Round to hundredths of an inch, which is plenty for an exclusion zone
+    let to_inches = |pixels: f64| (geometry::pixels_to_inches(pixels, pixels_per_point) * 100.0).round() / 100.0;
+
+    Rectangle {
+        page: page.to_string(),
+        top_left: [to_inches(pixel_region[0].min(pixel_region[2])), to_inches(pixel_region[1].min(pixel_region[3]))],
+        bottom_right: [to_inches(pixel_region[0].max(pixel_region[2])), to_inches(pixel_region[1].max(pixel_region[3]))],
+        unit: RectangleUnit::Inch,
+    }
+}
+
+
+// Parse a pixel region given on the command line as x1,y1,x2,y2
+pub fn parse_pixel_region(value: &str) -> Result<[f64; 4], String> {
+    let numbers: Vec<f64> = value.split(',')
+        .map(|number| number.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("'{}' is not a list of numbers like x1,y1,x2,y2", value))?;
+
+    match numbers[..] {
+        [x1, y1, x2, y2] => Ok([x1, y1, x2, y2]),
+        _ => Err(format!("'{}' should have four numbers: x1,y1,x2,y2", value)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_region_converts_to_an_inch_rectangle() {
+        // A letter page (792 points high) rendered 1584 pixels high: 2 pixels per point, 144 pixels per inch
+        let pixels_per_point = geometry::pixels_per_point(1584, 792.0);
+        let rectangle = pixels_to_inch_rectangle("3", [144.0, 288.0, 432.0, 720.0], pixels_per_point);
+
+        assert_eq!(rectangle.page, "3");
+        assert_eq!(rectangle.top_left, [1.0, 2.0]);
+        assert_eq!(rectangle.bottom_right, [3.0, 5.0]);
+        assert_eq!(rectangle.unit, RectangleUnit::Inch);
+    }
+
+    #[test]
+    fn pixel_region_corners_can_be_given_in_any_order_and_are_rounded() {
+        let pixels_per_point = geometry::pixels_per_point(1584, 792.0);
+        let rectangle = pixels_to_inch_rectangle("all", [433.0, 721.0, 145.0, 289.0], pixels_per_point);
+
+        assert_eq!(rectangle.top_left, [1.01, 2.01]);
+        assert_eq!(rectangle.bottom_right, [3.01, 5.01]);
+    }
+
+    #[test]
+    fn grid_lines_are_every_half_inch() {
+        let image = draw_inch_grid(&RgbaImage::from_pixel(300, 300, Rgba([255, 255, 255, 255])), 144.0);
+
+        // Inch lines at 0 and 144 pixels, half inch lines at 72 and 216 pixels
+        assert_eq!(*image.get_pixel(144, 10), Rgba([0, 120, 255, 255]));
+        assert_eq!(*image.get_pixel(10, 144), Rgba([0, 120, 255, 255]));
+        assert_eq!(*image.get_pixel(72, 10), Rgba([160, 200, 255, 255]));
+        assert_eq!(*image.get_pixel(10, 216), Rgba([160, 200, 255, 255]));
+        assert_eq!(*image.get_pixel(100, 100), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn pixel_regions_need_four_numbers() {
+        assert_eq!(parse_pixel_region("10, 20,30,40"), Ok([10.0, 20.0, 30.0, 40.0]));
+        assert!(parse_pixel_region("10,20,30").is_err());
+        assert!(parse_pixel_region("10,20,thirty,40").is_err());
+    }
+}
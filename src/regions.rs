@@ -0,0 +1,326 @@
+// Region related functions (groups of neighboring differing chunks)
+
+use std::collections::HashMap;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use crate::geometry;
+
+
+// A rectangular region of a page image in pixels (right and bottom are exclusive)
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+
+// A rectangular region of a PDF page in points, as PDF annotations place it: from the bottom left corner of the page, with y going up
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct PointRegion {
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+    pub top: f64,
+}
+
+
+// Content that appears in both documents, but at a different position
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MovedContent {
+    pub page: i32,
+    // Where the content is in the first document
+    pub from: Region,
+    // How far (in pixels) the content moved in the second document
+    pub displacement: [i32; 2],
+}
+
+
+impl Region {
+
+    pub fn width(&self) -> u32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> u32 {
+        self.bottom - self.top
+    }
+}
+
+
+// Merge differing chunks into regions: chunks that touch (including diagonally) end up in the same region.
+// The region is the bounding box of its chunks, clipped to the image size.
+pub fn merge_chunks(chunks: &[(u32, u32)], chunk_size: u32, width: u32, height: u32) -> Vec<Region> {
+    let mut regions = vec![];
+    let mut visited = vec![false; chunks.len()];
+
+    // Sort the chunks into grid cells of one chunk size, so only the chunks in the neighboring cells need to be looked at
+    // (looking at every other chunk for each chunk gets slow on pages with many differences)
+    let cell = |(x, y): (u32, u32)| (x / chunk_size.max(1), y / chunk_size.max(1));
+    let mut cells: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+
+    for (index, &chunk) in chunks.iter().enumerate() {
+        cells.entry(cell(chunk)).or_default().push(index);
+    }
+
+    for start in 0..chunks.len() {
+        if visited[start] {
+            continue;
+        }
+
+        visited[start] = true;
+
+        // Walk all the chunks connected to this one
+        let mut stack = vec![start];
+        let (mut left, mut top) = chunks[start];
+        let (mut right, mut bottom) = chunks[start];
+
+        while let Some(current) = stack.pop() {
+            let (x, y) = chunks[current];
+
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+
+            // Chunks at most one chunk size away are always in this cell or one of the eight around it
+            let (cell_x, cell_y) = cell((x, y));
+
+            for neighbor_y in cell_y.saturating_sub(1)..=cell_y + 1 {
+                for neighbor_x in cell_x.saturating_sub(1)..=cell_x + 1 {
+                    for &other in cells.get(&(neighbor_x, neighbor_y)).into_iter().flatten() {
+                        let (other_x, other_y) = chunks[other];
+
+                        if !visited[other] && x.abs_diff(other_x) <= chunk_size && y.abs_diff(other_y) <= chunk_size {
+                            visited[other] = true;
+                            stack.push(other);
+                        }
+                    }
+                }
+            }
+        }
+
+        regions.push(Region {
+            left,
+            top,
+            right: (right + chunk_size).min(width),
+            bottom: (bottom + chunk_size).min(height),
+        });
+    }
+
+    regions
+}
+
+
+// The bounding box of all the chunks (clipped to the image size), None if there are no chunks
+pub fn bounding_box(chunks: &[(u32, u32)], chunk_size: u32, width: u32, height: u32) -> Option<Region> {
+    let left = chunks.iter().map(|&(x, _)| x).min()?;
+    let top = chunks.iter().map(|&(_, y)| y).min()?;
+    let right = chunks.iter().map(|&(x, _)| x).max()?;
+    let bottom = chunks.iter().map(|&(_, y)| y).max()?;
+
+    Some(Region {
+        left,
+        top,
+        right: (right + chunk_size).min(width),
+        bottom: (bottom + chunk_size).min(height),
+    })
+}
+
+
+// Convert a region of a page image (in pixels, from the top left) to the same region of the PDF page (in points, from the bottom left).
+// This undoes the rendering: pixels_per_point is the factor used to render the page, and page_height_in_points its height.
+pub fn region_to_points(region: &Region, pixels_per_point: f64, page_height_in_points: f64) -> PointRegion {
+    let to_points = |pixels: u32| geometry::pixels_to_points(pixels as f64, pixels_per_point);
+
+    PointRegion {
+        left: to_points(region.left),
+        bottom: page_height_in_points - to_points(region.bottom),
+        right: to_points(region.right),
+        top: page_height_in_points - to_points(region.top),
+    }
+}
+
+
+// Look for regions whose content in the first image shows up at another differing region in the second image.
+// This is a template match of the pixels, searched within a chunk of the displacement between the two regions.
+// It's relatively expensive (it tries every nearby offset), so it is only used when asked for.
+pub fn detect_moves(img1: &RgbaImage, img2: &RgbaImage, regions: &[Region], chunk_size: u32, page: i32) -> Vec<MovedContent> {
+    let mut moves = vec![];
+    let mut used = vec![false; regions.len()];
+
+    for (from_index, from) in regions.iter().enumerate() {
+
+        // Only regions with actual content in the first document can have moved
+        if used[from_index] || !has_content(img1, from) {
+            continue;
+        }
+
+        for (to_index, to) in regions.iter().enumerate() {
+            if to_index == from_index || used[to_index] {
+                continue;
+            }
+
+            let base_dx = to.left as i32 - from.left as i32;
+            let base_dy = to.top as i32 - from.top as i32;
+
+            if let Some(displacement) = find_displacement(img1, img2, from, base_dx, base_dy, chunk_size as i32) {
+                used[from_index] = true;
+                used[to_index] = true;
+
+                moves.push(MovedContent {
+                    page,
+                    from: *from,
+                    displacement,
+                });
+
+                break;
+            }
+        }
+    }
+
+    moves
+}
+
+
+// Check if a region of an image contains anything other than (near) white background
+fn has_content(image: &RgbaImage, region: &Region) -> bool {
+    (region.top..region.bottom).any(|y| {
+        (region.left..region.right).any(|x| {
+            let pixel = image.get_pixel(x, y);
+            pixel[0] < 250 || pixel[1] < 250 || pixel[2] < 250
+        })
+    })
+}
+
+
+// Search offsets around (base_dx, base_dy) for one where the region of img1 matches img2
+fn find_displacement(img1: &RgbaImage, img2: &RgbaImage, region: &Region, base_dx: i32, base_dy: i32, search: i32) -> Option<[i32; 2]> {
+    // Compare every other pixel in each direction to keep the search affordable
+    let step = 2;
+    let sampled_pixels = ((region.width() / step).max(1) * (region.height() / step).max(1)) as usize;
+
+    // Allow a small number of mismatches (e.g. anti-aliasing at the edges of the content)
+    let allowed_mismatches = sampled_pixels / 100;
+
+    let mut best: Option<([i32; 2], usize)> = None;
+
+    for dy in (base_dy - search)..=(base_dy + search) {
+        for dx in (base_dx - search)..=(base_dx + search) {
+
+            // The moved region must fit inside the second image
+            if region.left as i32 + dx < 0 || region.top as i32 + dy < 0 ||
+                region.right as i32 + dx > img2.width() as i32 || region.bottom as i32 + dy > img2.height() as i32 {
+                continue;
+            }
+
+            let mut mismatches = 0;
+
+            'rows: for y in (region.top..region.bottom).step_by(step as usize) {
+                for x in (region.left..region.right).step_by(step as usize) {
+                    let moved_x = (x as i32 + dx) as u32;
+                    let moved_y = (y as i32 + dy) as u32;
+
+                    if img1.get_pixel(x, y) != img2.get_pixel(moved_x, moved_y) {
+                        mismatches += 1;
+
+                        if mismatches > allowed_mismatches {
+                            break 'rows;
+                        }
+                    }
+                }
+            }
+
+            if mismatches <= allowed_mismatches && best.is_none_or(|(_, best_mismatches)| mismatches < best_mismatches) {
+                best = Some(([dx, dy], mismatches));
+            }
+        }
+    }
+
+    best.map(|(displacement, _)| displacement)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn touching_chunks_merge_into_one_region() {
+        // Two chunks side by side, one diagonally below them, and one on its own
+        let regions = merge_chunks(&[(0, 0), (10, 0), (20, 10), (60, 60)], 10, 100, 100);
+
+        assert_eq!(regions, vec![
+            Region { left: 0, top: 0, right: 30, bottom: 20 },
+            Region { left: 60, top: 60, right: 70, bottom: 70 },
+        ]);
+    }
+
+    #[test]
+    fn regions_are_clipped_to_the_image() {
+        let regions = merge_chunks(&[(90, 90)], 10, 95, 98);
+
+        assert_eq!(regions, vec![Region { left: 90, top: 90, right: 95, bottom: 98 }]);
+        assert!(merge_chunks(&[], 10, 100, 100).is_empty());
+    }
+
+    // A white page with a striped block of content at (left, top), 30 x 20 pixels
+    fn page_with_block(left: u32, top: u32) -> RgbaImage {
+        RgbaImage::from_fn(100, 150, |x, y| {
+            if (left..left + 30).contains(&x) && (top..top + 20).contains(&y) && (x + y) % 3 != 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn shifted_block_is_one_move() {
+        let image1 = page_with_block(20, 20);
+        let image2 = page_with_block(20, 80);
+
+        // The chunks that differ: where the block was, and where it is now
+        let chunks: Vec<(u32, u32)> = [20, 30, 80, 90].iter().flat_map(|&y| [20, 30, 40].map(|x| (x, y))).collect();
+        let regions = merge_chunks(&chunks, 10, 100, 150);
+
+        assert_eq!(regions.len(), 2);
+
+        let moves = detect_moves(&image1, &image2, &regions, 10, 3);
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].page, 3);
+        assert_eq!(moves[0].from, Region { left: 20, top: 20, right: 50, bottom: 40 });
+        assert_eq!(moves[0].displacement, [0, 60]);
+    }
+
+    #[test]
+    fn different_content_is_not_a_move() {
+        let image1 = page_with_block(20, 20);
+        let image2 = RgbaImage::from_pixel(100, 150, Rgba([255, 255, 255, 255]));
+
+        let chunks: Vec<(u32, u32)> = [20, 30, 80, 90].iter().flat_map(|&y| [20, 30, 40].map(|x| (x, y))).collect();
+
+        assert!(detect_moves(&image1, &image2, &merge_chunks(&chunks, 10, 100, 150), 10, 1).is_empty());
+    }
+
+    #[test]
+    fn chunks_that_are_not_on_the_grid_still_merge() {
+        // Chunks off the chunk grid, touching across a grid cell boundary
+        let regions = merge_chunks(&[(5, 5), (14, 15), (30, 5)], 10, 100, 100);
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0], Region { left: 5, top: 5, right: 24, bottom: 25 });
+    }
+
+    #[test]
+    fn a_full_page_of_chunks_merges_into_one_region() {
+        // Every chunk of a 2000 x 2000 pixel page, too many to compare each chunk with all the others
+        let chunks: Vec<(u32, u32)> = (0..200).flat_map(|y| (0..200).map(move |x| (x * 10, y * 10))).collect();
+        let regions = merge_chunks(&chunks, 10, 2000, 2000);
+
+        assert_eq!(regions, vec![Region { left: 0, top: 0, right: 2000, bottom: 2000 }]);
+    }
+}
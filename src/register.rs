@@ -0,0 +1,193 @@
+// Registration related functions (align a scanned page onto the other document's page before comparing)
+
+use image::{Rgba, RgbaImage};
+use crate::deskew;
+
+
+// The largest shift (as a percentage of the page width or height) that is looked for
+const MAX_SHIFT_PERCENT: f64 = 5.0;
+
+// Rotations smaller than this (in degrees) are left alone, as rotating would only blur the page
+const MIN_CORRECTED_ROTATION_DEGREES: f64 = 0.05;
+
+
+// How the second page is moved to line up with the first: rotated (in degrees), then shifted (in pixels)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Registration {
+    pub degrees: f64,
+    pub shift_x: i32,
+    pub shift_y: i32,
+}
+
+
+// Estimate how the content of image2 is rotated and shifted relative to image1.
+// The rotation is the difference of the skews of the two pages (see deskew::estimate_skew), so only small rotations are found.
+// The shift is found by correlating how dark the rows (and columns) of the two pages are, after undoing the rotation:
+// a one dimensional stand-in for phase correlation, which only finds shifts of up to MAX_SHIFT_PERCENT of the page.
+pub fn estimate_registration(image1: &RgbaImage, image2: &RgbaImage) -> Registration {
+    let mut degrees = deskew::estimate_skew(image2) - deskew::estimate_skew(image1);
+
+    if degrees.abs() < MIN_CORRECTED_ROTATION_DEGREES {
+        degrees = 0.0;
+    }
+
+    let rotated_image2;
+    let image2 = if degrees == 0.0 {
+        image2
+    } else {
+        rotated_image2 = deskew::rotate_to_level(image2, degrees);
+        &rotated_image2
+    };
+
+    let (rows1, columns1) = darkness_profiles(image1);
+    let (rows2, columns2) = darkness_profiles(image2);
+
+    Registration {
+        degrees,
+        shift_x: best_shift(&columns1, &columns2),
+        shift_y: best_shift(&rows1, &rows2),
+    }
+}
+
+
+// Move the content of image2 to line up with image1.  Returns the image and the registration that was applied.
+// The image keeps its size; areas moved in from outside the page are filled with white.
+pub fn register(image1: &RgbaImage, image2: RgbaImage) -> (RgbaImage, Registration) {
+    let registration = estimate_registration(image1, &image2);
+
+    if registration.degrees == 0.0 && registration.shift_x == 0 && registration.shift_y == 0 {
+        return (image2, registration);
+    }
+
+    let rotated_image2 = if registration.degrees == 0.0 {
+        image2
+    } else {
+        deskew::rotate_to_level(&image2, registration.degrees)
+    };
+
+    (shift_image(&rotated_image2, registration.shift_x, registration.shift_y), registration)
+}
+
+
+// How dark each row and each column of the image is (the sum of how far each pixel is from white)
+fn darkness_profiles(image: &RgbaImage) -> (Vec<f64>, Vec<f64>) {
+    let (width, height) = image.dimensions();
+    let mut rows = vec![0.0; height as usize];
+    let mut columns = vec![0.0; width as usize];
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let darkness = (3 * 255 - (pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32)) as f64;
+
+        rows[y as usize] += darkness;
+        columns[x as usize] += darkness;
+    }
+
+    (rows, columns)
+}
+
+
+// Find the shift of profile2 (in pixels) that best lines it up with profile1: content at position i of profile2 belongs at i - shift.
+// The profiles are compared with their lowest value (the background of the page) taken off, so blank margins add nothing to the score.
+fn best_shift(profile1: &[f64], profile2: &[f64]) -> i32 {
+    let length = profile1.len().min(profile2.len());
+
+    if length == 0 {
+        return 0;
+    }
+
+    let above_background = |profile: &[f64]| {
+        let background = profile[..length].iter().cloned().fold(f64::MAX, f64::min);
+
+        profile[..length].iter().map(|value| value - background).collect::<Vec<f64>>()
+    };
+
+    let (profile1, profile2) = (above_background(profile1), above_background(profile2));
+    let max_shift = (length as f64 * MAX_SHIFT_PERCENT / 100.0).round() as i32;
+
+    let mut best_shift: i32 = 0;
+    let mut best_score = f64::MIN;
+
+    for shift in -max_shift..=max_shift {
+        let score: f64 = (0..length as i32)
+            .filter_map(|i| {
+                let j = i + shift;
+
+                (j >= 0 && j < length as i32).then(|| profile1[i as usize] * profile2[j as usize])
+            })
+            .sum();
+
+        // Prefer the smallest shift when scores are equal
+        if score > best_score || (score == best_score && shift.abs() < best_shift.abs()) {
+            best_score = score;
+            best_shift = shift;
+        }
+    }
+
+    best_shift
+}
+
+
+// Move the content of the image by (-shift_x, -shift_y) pixels, filling the uncovered area with white
+fn shift_image(image: &RgbaImage, shift_x: i32, shift_y: i32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (source_x, source_y) = (x as i64 + shift_x as i64, y as i64 + shift_y as i64);
+
+        if source_x >= 0 && source_y >= 0 && source_x < width as i64 && source_y < height as i64 {
+            *image.get_pixel(source_x as u32, source_y as u32)
+        } else {
+            Rgba([255, 255, 255, 255])
+        }
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A page with a few dark blocks of different sizes (like a heading and lines of text), moved by (dx, dy) pixels
+    fn synthetic_page(dx: i32, dy: i32) -> RgbaImage {
+        let blocks = [(60, 50, 220, 80), (60, 120, 340, 135), (60, 150, 300, 165), (60, 180, 330, 195), (250, 300, 350, 420)];
+
+        RgbaImage::from_fn(400, 500, |x, y| {
+            let (x, y) = (x as i32 - dx, y as i32 - dy);
+
+            if blocks.iter().any(|&(left, top, right, bottom)| x >= left && x < right && y >= top && y < bottom) {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn register_recovers_a_known_shift() {
+        let image1 = synthetic_page(0, 0);
+        let (registered_image2, registration) = register(&image1, synthetic_page(12, -7));
+
+        assert_eq!(registration.degrees, 0.0);
+        assert_eq!((registration.shift_x, registration.shift_y), (12, -7));
+
+        // Once shifted back, the second page lines up with the first
+        assert_eq!(registered_image2, image1);
+    }
+
+    #[test]
+    fn register_leaves_a_page_that_lines_up_alone() {
+        let image1 = synthetic_page(0, 0);
+        let (registered_image2, registration) = register(&image1, synthetic_page(0, 0));
+
+        assert_eq!((registration.degrees, registration.shift_x, registration.shift_y), (0.0, 0, 0));
+        assert_eq!(registered_image2, image1);
+    }
+
+    #[test]
+    fn shifts_beyond_the_search_range_are_not_found() {
+        // 5% of 400 pixels is 20 pixels, so a shift of 60 pixels is out of reach
+        let registration = estimate_registration(&synthetic_page(0, 0), &synthetic_page(60, 0));
+
+        assert!(registration.shift_x.abs() <= 20);
+    }
+}
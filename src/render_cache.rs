@@ -0,0 +1,153 @@
+// Render cache related functions (keep rendered page images on disk, so an unchanged document doesn't have to be rendered again)
+
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+use pdfium_render::prelude::*;
+use crate::images;
+use log::error;
+
+
+pub struct RenderCache {
+    dir: PathBuf,
+    // A key for each document, from its contents and the settings it is rendered with.
+    // Any change to either gives a new key, so stale images are never used.
+    document_keys: [String; 2],
+    // How many pages were loaded from the cache, and how many had to be rendered
+    pub hits: Cell<u32>,
+    pub misses: Cell<u32>,
+}
+
+
+impl RenderCache {
+
+    pub fn new(dir: &Path, document_paths: [&Path; 2], render_settings: [String; 2]) -> std::io::Result<RenderCache> {
+        let document_key = |path: &Path, settings: &str| -> std::io::Result<String> {
+            // The standard hasher is fine for this: a different hash after a Rust upgrade only means a cache miss
+            let mut hasher = DefaultHasher::new();
+            std::fs::read(path)?.hash(&mut hasher);
+            settings.hash(&mut hasher);
+
+            Ok(format!("{:016x}", hasher.finish()))
+        };
+
+        Ok(RenderCache {
+            dir: dir.to_path_buf(),
+            document_keys: [
+                document_key(document_paths[0], &render_settings[0])?,
+                document_key(document_paths[1], &render_settings[1])?,
+            ],
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        })
+    }
+
+
+    // The image of a page of the first (0) or second (1) document, from the cache if it's there.
+    // Otherwise the page is rendered and added to the cache.
+    pub fn render_page(&self, document: usize, page: &PdfPage, page_index: u16, render_config: &PdfRenderConfig) -> Result<RgbaImage, Box<dyn Error>> {
+        self.cached_image(document, page_index, || images::render_page(page, render_config))
+    }
+
+
+    // The image of a page from the cache if it's there; otherwise it is rendered (by 'render') and added to the cache
+    fn cached_image(&self, document: usize, page_index: u16, render: impl FnOnce() -> Result<RgbaImage, PdfiumError>) -> Result<RgbaImage, Box<dyn Error>> {
+        let path = self.dir.join(format!("{}-page-{:04}.png", self.document_keys[document], page_index + 1));
+
+        if path.exists() {
+            if let Ok(image) = image::open(&path) {
+                self.hits.set(self.hits.get() + 1);
+
+                return Ok(image.to_rgba8());
+            }
+        }
+
+        self.misses.set(self.misses.get() + 1);
+
+        let image = render()?;
+
+        // Failing to write to the cache shouldn't stop the comparison
+        if let Err(e) = image.save(&path) {
+            error!("Error writing to the render cache {}: {}", path.display(), e);
+        }
+
+        Ok(image)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // A new, empty folder for a test, with a document (its contents) for each side
+    fn test_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("match_pdf-render-cache-{}-{}", name, std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("first.pdf"), b"first document").unwrap();
+        std::fs::write(folder.join("second.pdf"), b"second document").unwrap();
+
+        folder
+    }
+
+    fn cache(folder: &Path, render_settings: &str) -> RenderCache {
+        RenderCache::new(folder, [&folder.join("first.pdf"), &folder.join("second.pdf")], [render_settings.to_string(), render_settings.to_string()]).unwrap()
+    }
+
+    // Get a page image from the cache, counting how often it had to be rendered
+    fn get_page(cache: &RenderCache, document: usize, page_index: u16, renders: &Cell<u32>) -> RgbaImage {
+        cache.cached_image(document, page_index, || {
+            renders.set(renders.get() + 1);
+
+            Ok(RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255])))
+        }).unwrap()
+    }
+
+    #[test]
+    fn second_comparison_reads_from_the_cache() {
+        let folder = test_folder("hits");
+        let renders = Cell::new(0);
+
+        let first_run = cache(&folder, "2000");
+        get_page(&first_run, 0, 0, &renders);
+        get_page(&first_run, 0, 1, &renders);
+
+        assert_eq!((renders.get(), first_run.hits.get(), first_run.misses.get()), (2, 0, 2));
+
+        // Comparing the same document again loads its pages instead of rendering them
+        let second_run = cache(&folder, "2000");
+        let image = get_page(&second_run, 0, 0, &renders);
+        get_page(&second_run, 0, 1, &renders);
+
+        assert_eq!((renders.get(), second_run.hits.get(), second_run.misses.get()), (2, 2, 0));
+        assert_eq!(*image.get_pixel(0, 0), Rgba([10, 20, 30, 255]));
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn other_documents_and_render_settings_are_not_read_from_the_cache() {
+        let folder = test_folder("misses");
+        let renders = Cell::new(0);
+
+        get_page(&cache(&folder, "2000"), 0, 0, &renders);
+
+        // The second document has other contents, and another render width gives another key
+        let second_document = cache(&folder, "2000");
+        get_page(&second_document, 1, 0, &renders);
+
+        let other_settings = cache(&folder, "3000");
+        get_page(&other_settings, 0, 0, &renders);
+
+        assert_eq!(renders.get(), 3);
+        assert_eq!(second_document.misses.get() + other_settings.misses.get(), 2);
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
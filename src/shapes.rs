@@ -0,0 +1,393 @@
+// Exclusion zone related functions (the rectangles, circles and polygons of a config file that are not compared)
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use crate::{Rectangle, RectangleUnit};
+
+
+// An area of a page that is left out of the comparison.
+// The pixel coordinates are those of the page image, after in_pixels has converted the shape from the units of the config file.
+pub trait IgnoreShape: Debug + Send + Sync {
+
+    // The page value of the shape ("all", "even", "odd", a page number, a range or a list of these)
+    fn page(&self) -> &str;
+
+    // A copy of the shape with its coordinates converted to pixels of the page image
+    fn in_pixels(&self, pixels_per_point: f64) -> Box<dyn IgnoreShape>;
+
+    // Describe the shape with its coordinates as given, for the plan
+    fn describe(&self) -> String;
+
+    // The smallest rectangle holding the shape, as [left, top, right, bottom]
+    fn bounding_box(&self) -> [f64; 4];
+
+    // Check if the point (x, y) lies inside the shape
+    fn contains(&self, x: u32, y: u32) -> bool;
+
+    // Check if the shape may reach into the chunk with its top left corner at (x, y).
+    // It is fine to answer true for a chunk the shape misses (the pixels of the chunk are then checked one by one).
+    fn overlaps(&self, x: u32, y: u32, chunk_size: u32) -> bool {
+        let [left, top, right, bottom] = self.bounding_box();
+
+        !((right as u32) < x || (left as u32) > x + chunk_size || (bottom as u32) < y || (top as u32) > y + chunk_size)
+    }
+
+    // Check if the shape has no area: it is inverted (its top left is not above and to the left of its bottom right),
+    // or a circle without a radius. This doesn't depend on the unit of the shape.
+    fn is_empty(&self) -> bool {
+        let [left, top, right, bottom] = self.bounding_box();
+
+        left >= right || top >= bottom
+    }
+
+    // Check if a chunk is completely inside the shape, so it isn't compared at all.
+    // Checking the corners is enough for shapes without dents (rectangles and circles).
+    fn covers_chunk(&self, x: u32, y: u32, chunk_size: u32) -> bool {
+        self.overlaps(x, y, chunk_size) &&
+        self.contains(x, y) &&
+        self.contains(x + chunk_size, y) &&
+        self.contains(x, y + chunk_size) &&
+        self.contains(x + chunk_size, y + chunk_size)
+    }
+}
+
+
+// The shapes that apply to a page
+pub type IgnoreShapes = Vec<Box<dyn IgnoreShape>>;
+
+
+// An excluded circle: the center and the radius, in the unit of the shape
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Circle {
+    pub page: String,
+    pub center: [f64; 2],
+    pub radius: f64,
+    #[serde(default, skip_serializing_if = "RectangleUnit::is_inch")]
+    pub unit: RectangleUnit,
+}
+
+
+// An excluded polygon: its corners in order, in the unit of the shape (the last corner connects back to the first)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Polygon {
+    pub page: String,
+    pub points: Vec<[f64; 2]>,
+    #[serde(default, skip_serializing_if = "RectangleUnit::is_inch")]
+    pub unit: RectangleUnit,
+}
+
+
+impl IgnoreShape for Rectangle {
+
+    fn page(&self) -> &str {
+        &self.page
+    }
+
+    fn in_pixels(&self, pixels_per_point: f64) -> Box<dyn IgnoreShape> {
+        let to_pixels = |value: f64| self.unit.to_pixels(value, pixels_per_point);
+
+        Box::new(Rectangle {
+            page: self.page.clone(),
+            top_left: [to_pixels(self.top_left[0]), to_pixels(self.top_left[1])],
+            bottom_right: [to_pixels(self.bottom_right[0]), to_pixels(self.bottom_right[1])],
+            unit: RectangleUnit::Pixel,
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("rectangle from ({}, {}) to ({}, {}) {}", self.top_left[0], self.top_left[1], self.bottom_right[0], self.bottom_right[1], self.unit.plural())
+    }
+
+    fn bounding_box(&self) -> [f64; 4] {
+        [self.top_left[0], self.top_left[1], self.bottom_right[0], self.bottom_right[1]]
+    }
+
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.top_left[0] as u32 &&
+        x <= self.bottom_right[0] as u32 &&
+        y >= self.top_left[1] as u32 &&
+        y <= self.bottom_right[1] as u32
+    }
+}
+
+
+impl IgnoreShape for Circle {
+
+    fn page(&self) -> &str {
+        &self.page
+    }
+
+    fn in_pixels(&self, pixels_per_point: f64) -> Box<dyn IgnoreShape> {
+        let to_pixels = |value: f64| self.unit.to_pixels(value, pixels_per_point);
+
+        Box::new(Circle {
+            page: self.page.clone(),
+            center: [to_pixels(self.center[0]), to_pixels(self.center[1])],
+            radius: to_pixels(self.radius),
+            unit: RectangleUnit::Pixel,
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("circle around ({}, {}) with a radius of {} {}", self.center[0], self.center[1], self.radius, self.unit.plural())
+    }
+
+    fn bounding_box(&self) -> [f64; 4] {
+        [self.center[0] - self.radius, self.center[1] - self.radius, self.center[0] + self.radius, self.center[1] + self.radius]
+    }
+
+    fn contains(&self, x: u32, y: u32) -> bool {
+        let (dx, dy) = (x as f64 - self.center[0], y as f64 - self.center[1]);
+
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+
+    // The circle reaches into the chunk if the point of the chunk closest to the center is inside it
+    fn overlaps(&self, x: u32, y: u32, chunk_size: u32) -> bool {
+        let closest_x = self.center[0].clamp(x as f64, (x + chunk_size) as f64);
+        let closest_y = self.center[1].clamp(y as f64, (y + chunk_size) as f64);
+        let (dx, dy) = (closest_x - self.center[0], closest_y - self.center[1]);
+
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+}
+
+
+impl IgnoreShape for Polygon {
+
+    fn page(&self) -> &str {
+        &self.page
+    }
+
+    fn in_pixels(&self, pixels_per_point: f64) -> Box<dyn IgnoreShape> {
+        let to_pixels = |value: f64| self.unit.to_pixels(value, pixels_per_point);
+
+        Box::new(Polygon {
+            page: self.page.clone(),
+            points: self.points.iter().map(|point| [to_pixels(point[0]), to_pixels(point[1])]).collect(),
+            unit: RectangleUnit::Pixel,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let points: Vec<String> = self.points.iter().map(|point| format!("({}, {})", point[0], point[1])).collect();
+
+        format!("polygon {} {}", points.join(" "), self.unit.plural())
+    }
+
+    fn bounding_box(&self) -> [f64; 4] {
+        self.points.iter().fold([f64::MAX, f64::MAX, f64::MIN, f64::MIN], |[left, top, right, bottom], point| {
+            [left.min(point[0]), top.min(point[1]), right.max(point[0]), bottom.max(point[1])]
+        })
+    }
+
+    // Even-odd rule: the point is inside if a ray going right from it crosses the sides of the polygon an odd number of times.
+    // A polygon with fewer than three corners has no inside.
+    fn contains(&self, x: u32, y: u32) -> bool {
+        if self.points.len() < 3 {
+            return false;
+        }
+
+        let (x, y) = (x as f64, y as f64);
+        let mut inside = false;
+
+        for (start, end) in self.sides() {
+            if (start[1] > y) != (end[1] > y) {
+                let crossing_x = start[0] + (y - start[1]) * (end[0] - start[0]) / (end[1] - start[1]);
+
+                if x < crossing_x {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+
+    // A polygon can have dents, so the corners of the chunk being inside isn't enough: no side may cross the chunk either
+    fn covers_chunk(&self, x: u32, y: u32, chunk_size: u32) -> bool {
+        let chunk = [x as f64, y as f64, (x + chunk_size) as f64, (y + chunk_size) as f64];
+
+        self.contains(x, y) && !self.sides().any(|(start, end)| segment_crosses_box(start, end, chunk))
+    }
+}
+
+
+impl Polygon {
+
+    // The sides of the polygon, as pairs of corners
+    fn sides(&self) -> impl Iterator<Item = ([f64; 2], [f64; 2])> + '_ {
+        self.points.iter().zip(self.points.iter().cycle().skip(1)).map(|(start, end)| (*start, *end))
+    }
+}
+
+
+// Check if any part of the line segment from start to end lies in the box [left, top, right, bottom] (Liang-Barsky clipping)
+fn segment_crosses_box(start: [f64; 2], end: [f64; 2], [left, top, right, bottom]: [f64; 4]) -> bool {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    let (mut entering, mut leaving) = (0.0_f64, 1.0_f64);
+
+    for (direction, distance) in [(-dx, start[0] - left), (dx, right - start[0]), (-dy, start[1] - top), (dy, bottom - start[1])] {
+        if direction == 0.0 {
+            // Parallel to this edge of the box, and outside it
+            if distance < 0.0 {
+                return false;
+            }
+        } else {
+            let fraction = distance / direction;
+
+            if direction < 0.0 {
+                entering = entering.max(fraction);
+            } else {
+                leaving = leaving.min(fraction);
+            }
+        }
+    }
+
+    entering <= leaving
+}
+
+
+// Describe what is wrong with where a shape (in pixels) lands on a page image of the given size, if anything.
+// A shape may reach a pixel past the edges, as converting from inches or points rounds its coordinates.
+pub fn placement_problem(shape: &dyn IgnoreShape, width: u32, height: u32) -> Option<&'static str> {
+    let [left, top, right, bottom] = shape.bounding_box();
+    let (width, height) = (width as f64, height as f64);
+
+    if shape.is_empty() {
+        Some("is inverted or empty (its top left is not above and to the left of its bottom right), so it excludes nothing")
+    } else if right < 0.0 || bottom < 0.0 || left >= width || top >= height {
+        Some("lies entirely outside the page, so it excludes nothing")
+    } else if left < -1.0 || top < -1.0 || right > width + 1.0 || bottom > height + 1.0 {
+        Some("reaches outside the page")
+    } else {
+        None
+    }
+}
+
+
+// Bands across the full width of the top and bottom of every page (e.g. page numbers and timestamps in the margins),
+// as a percentage of the height of the page
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PageBands {
+    pub top_percent: Option<f64>,
+    pub bottom_percent: Option<f64>,
+}
+
+impl PageBands {
+
+    // The rectangles (in pixels) covering the bands of a page image of the given size
+    pub fn rectangles(&self, width: u32, height: u32) -> IgnoreShapes {
+        let band_height = |percent: f64| (height as f64 * percent / 100.0).round();
+        let (right, bottom) = ((width as f64 - 1.0).max(0.0), (height as f64 - 1.0).max(0.0));
+
+        let mut rectangles: IgnoreShapes = Vec::new();
+
+        if let Some(top_height) = self.top_percent.map(band_height).filter(|&band| band >= 1.0) {
+            rectangles.push(Box::new(Rectangle { page: "all".to_string(), top_left: [0.0, 0.0], bottom_right: [right, top_height - 1.0], unit: RectangleUnit::Pixel }));
+        }
+
+        if let Some(bottom_height) = self.bottom_percent.map(band_height).filter(|&band| band >= 1.0) {
+            rectangles.push(Box::new(Rectangle { page: "all".to_string(), top_left: [0.0, height as f64 - bottom_height], bottom_right: [right, bottom], unit: RectangleUnit::Pixel }));
+        }
+
+        rectangles
+    }
+}
+
+
+// Parse the height of a band given on the command line, as a percentage of the page height (more than 0, less than 100)
+pub fn parse_band_percent(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(percent) if percent > 0.0 && percent < 100.0 => Ok(percent),
+        Ok(_) => Err(format!("'{}' has to be more than 0 and less than 100 (percent of the page height)", value)),
+        Err(_) => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[[f64; 2]]) -> Polygon {
+        Polygon { page: "all".to_string(), points: points.to_vec(), unit: RectangleUnit::Pixel }
+    }
+
+    #[test]
+    fn polygon_contains_points_by_the_even_odd_rule() {
+        // A U shape: the dent between its arms is outside
+        let u_shape = polygon(&[[0.0, 0.0], [10.0, 0.0], [10.0, 20.0], [20.0, 20.0], [20.0, 0.0], [30.0, 0.0], [30.0, 30.0], [0.0, 30.0]]);
+
+        assert!(u_shape.contains(5, 10));
+        assert!(u_shape.contains(25, 10));
+        assert!(u_shape.contains(15, 25));
+        assert!(!u_shape.contains(15, 10));
+        assert!(!u_shape.contains(35, 10));
+
+        // A self-crossing star (a pentagram): its center crosses the sides twice going right, so it is outside
+        let star = polygon(&[[50.0, 0.0], [79.0, 90.0], [2.0, 35.0], [98.0, 35.0], [21.0, 90.0]]);
+
+        assert!(!star.contains(50, 50));
+        assert!(star.contains(50, 10));
+        assert!(!star.contains(5, 5));
+    }
+
+    #[test]
+    fn polygon_needs_three_corners() {
+        assert!(!polygon(&[[0.0, 0.0], [10.0, 10.0]]).contains(5, 5));
+        assert!(polygon(&[[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]).contains(2, 2));
+    }
+
+    #[test]
+    fn polygon_covers_chunks_the_dent_does_not_reach() {
+        let u_shape = polygon(&[[0.0, 0.0], [10.0, 0.0], [10.0, 20.0], [20.0, 20.0], [20.0, 0.0], [30.0, 0.0], [30.0, 30.0], [0.0, 30.0]]);
+
+        // The corners of a chunk across the dent are all inside, but a side of the dent crosses the chunk
+        assert!(!u_shape.covers_chunk(5, 15, 20));
+        assert!(u_shape.covers_chunk(21, 1, 8));
+        assert!(!u_shape.covers_chunk(25, 25, 10));
+    }
+
+    #[test]
+    fn segments_are_clipped_to_a_box() {
+        let chunk = [10.0, 10.0, 20.0, 20.0];
+
+        assert!(segment_crosses_box([0.0, 15.0], [30.0, 15.0], chunk));
+        assert!(segment_crosses_box([12.0, 12.0], [14.0, 14.0], chunk));
+        assert!(segment_crosses_box([0.0, 0.0], [30.0, 30.0], chunk));
+        assert!(!segment_crosses_box([0.0, 0.0], [9.0, 30.0], chunk));
+        assert!(!segment_crosses_box([0.0, 25.0], [30.0, 25.0], chunk));
+        assert!(!segment_crosses_box([0.0, 12.0], [5.0, 0.0], chunk));
+    }
+
+    #[test]
+    fn band_percent_is_more_than_0_and_less_than_100() {
+        assert_eq!(parse_band_percent("5"), Ok(5.0));
+        assert_eq!(parse_band_percent(" 12.5 "), Ok(12.5));
+        assert!(parse_band_percent("0").is_err());
+        assert!(parse_band_percent("100").is_err());
+        assert!(parse_band_percent("-3").is_err());
+        assert!(parse_band_percent("ten").is_err());
+    }
+
+    #[test]
+    fn bands_cover_the_full_width_at_the_top_and_bottom() {
+        let bands = PageBands { top_percent: Some(10.0), bottom_percent: Some(5.0) };
+        let rectangles = bands.rectangles(200, 300);
+
+        assert_eq!(rectangles.len(), 2);
+        assert_eq!(rectangles[0].bounding_box(), [0.0, 0.0, 199.0, 29.0]);
+        assert_eq!(rectangles[1].bounding_box(), [0.0, 285.0, 199.0, 299.0]);
+
+        // The rows just outside the bands are compared
+        assert!(rectangles[0].contains(199, 29) && !rectangles[0].contains(0, 30));
+        assert!(rectangles[1].contains(0, 285) && !rectangles[1].contains(0, 284));
+    }
+
+    #[test]
+    fn bands_less_than_a_pixel_high_are_left_out() {
+        assert!(PageBands::default().rectangles(200, 300).is_empty());
+        assert!(PageBands { top_percent: Some(0.1), bottom_percent: None }.rectangles(200, 300).is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+// Stacked image related functions (the pages of the output in one tall PNG image, e.g. to share in a chat)
+
+use image::{imageops, ImageResult, Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use crate::numbered_output_path;
+
+
+// The tallest image that is written; more pages go to the next numbered image
+const MAX_STACKED_IMAGE_HEIGHT: u32 = 30000;
+
+// The height of the gray band between two pages
+const SEPARATOR_HEIGHT: u32 = 20;
+
+const SEPARATOR_COLOR: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+
+// The pages (combined images) of the output stacked on top of each other, written as one or more PNG images
+pub struct StackedImage {
+    path: PathBuf,
+    pages: Vec<RgbaImage>,
+    height: u32,
+    // The number of the next image, if the pages don't fit in one
+    file_number: u32,
+    written: Vec<PathBuf>,
+}
+
+
+impl StackedImage {
+
+    pub fn new(path: &Path) -> StackedImage {
+        StackedImage { path: path.to_path_buf(), pages: Vec::new(), height: 0, file_number: 1, written: Vec::new() }
+    }
+
+    // Add a page below the others.  If it would make the image too tall, the pages so far are written first (as a numbered image).
+    pub fn add(&mut self, page_image: &RgbaImage) -> ImageResult<()> {
+        if !self.pages.is_empty() && self.height + SEPARATOR_HEIGHT + page_image.height() > MAX_STACKED_IMAGE_HEIGHT {
+            let numbered_path = numbered_output_path(&self.path, self.file_number);
+
+            self.write(numbered_path)?;
+        }
+
+        if !self.pages.is_empty() {
+            self.height += SEPARATOR_HEIGHT;
+        }
+
+        self.height += page_image.height();
+        self.pages.push(page_image.clone());
+
+        Ok(())
+    }
+
+    // Write the remaining pages: to the path itself if they all fit in one image, or else as the last numbered image.
+    // Returns the paths of all the images written, which is empty if no page was added (and no image was written).
+    pub fn finish(mut self) -> ImageResult<Vec<PathBuf>> {
+        if !self.pages.is_empty() {
+            let path = if self.written.is_empty() { self.path.clone() } else { numbered_output_path(&self.path, self.file_number) };
+
+            self.write(path)?;
+        }
+
+        Ok(self.written)
+    }
+
+    // Stack the pages so far, with a separator between them, and write them to the path
+    fn write(&mut self, path: PathBuf) -> ImageResult<()> {
+        let width = self.pages.iter().map(|page| page.width()).max().unwrap_or(1);
+        let mut stacked_image = RgbaImage::from_pixel(width, self.height.max(1), Rgba([255, 255, 255, 255]));
+
+        let mut y = 0;
+
+        for (number, page) in self.pages.iter().enumerate() {
+            if number > 0 {
+                for separator_y in y..y + SEPARATOR_HEIGHT {
+                    for x in 0..width {
+                        stacked_image.put_pixel(x, separator_y, SEPARATOR_COLOR);
+                    }
+                }
+
+                y += SEPARATOR_HEIGHT;
+            }
+
+            imageops::replace(&mut stacked_image, page, 0, y as i64);
+            y += page.height();
+        }
+
+        stacked_image.save(&path)?;
+
+        self.written.push(path);
+        self.pages.clear();
+        self.height = 0;
+        self.file_number += 1;
+
+        Ok(())
+    }
+}
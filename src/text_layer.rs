@@ -0,0 +1,92 @@
+// Text layer related functions (compare the text of the pages, to tell what text changed)
+
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+// The text that differs on a page
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PageTextDifference {
+    pub page: i32,
+    // Lines of text only in the first document, and lines only in the second document
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    // Why the text of the page couldn't be compared (e.g. a scanned page without a text layer)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+
+// Extract the text of the page as a list of lines, leaving out blank lines
+pub fn page_text_lines(page: &PdfPage) -> Result<Vec<String>, PdfiumError> {
+    Ok(page.text()?.all()
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+
+// Compare the lines of text of a page of each document, None if the text is the same.
+// A page without any text (e.g. a scan) is noted rather than reported as matching, or as having all its text removed.
+pub fn compare_page_text(page: i32, lines1: &[String], lines2: &[String]) -> Option<PageTextDifference> {
+    let note = match (lines1.is_empty(), lines2.is_empty()) {
+        (true, true) => Some("no extractable text"),
+        (true, false) => Some("no extractable text in the first document"),
+        (false, true) => Some("no extractable text in the second document"),
+        (false, false) => None,
+    };
+
+    if let Some(note) = note {
+        return Some(PageTextDifference { page, removed: Vec::new(), added: Vec::new(), note: Some(note.to_string()) });
+    }
+
+    let (removed, added) = diff_lines(lines1, lines2);
+
+    if removed.is_empty() && added.is_empty() {
+        None
+    } else {
+        Some(PageTextDifference { page, removed, added, note: None })
+    }
+}
+
+
+// Find the lines removed from the first list and added in the second, keeping the longest common sequence of lines in place
+fn diff_lines(lines1: &[String], lines2: &[String]) -> (Vec<String>, Vec<String>) {
+    // common[i][j] is the length of the longest common sequence of lines1[i..] and lines2[j..]
+    let mut common = vec![vec![0usize; lines2.len() + 1]; lines1.len() + 1];
+
+    for i in (0..lines1.len()).rev() {
+        for j in (0..lines2.len()).rev() {
+            common[i][j] = if lines1[i] == lines2[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < lines1.len() && j < lines2.len() {
+        if lines1[i] == lines2[j] {
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            removed.push(lines1[i].clone());
+            i += 1;
+        } else {
+            added.push(lines2[j].clone());
+            j += 1;
+        }
+    }
+
+    removed.extend(lines1[i..].iter().cloned());
+    added.extend(lines2[j..].iter().cloned());
+
+    (removed, added)
+}
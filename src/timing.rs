@@ -0,0 +1,38 @@
+// Timing related functions (where the time of a comparison goes, for the debug output)
+
+use log::debug;
+use std::time::Duration;
+
+
+// The wall-clock time spent on each step of comparing a page
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PageTimings {
+    pub page: u16,
+    // Rendering the pages to images (including confirming small differences at a higher resolution)
+    pub render: Duration,
+    // Comparing the images, chunk by chunk
+    pub compare: Duration,
+    // Highlighting the differences and adding the page to the output file or image folder
+    pub output: Duration,
+}
+
+
+// Log (at the debug level) a table with the time each step took on each page, and in total.
+// The output file is saved once for the whole document, so saving it only adds to the total.
+pub fn print_summary(page_timings: &[PageTimings], saving: Duration) {
+    let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+    debug!("Timing (milliseconds):");
+    debug!("{:>8} {:>12} {:>12} {:>12}", "page", "render", "compare", "output");
+
+    for timings in page_timings {
+        debug!("{:>8} {:>12.1} {:>12.1} {:>12.1}", timings.page, milliseconds(timings.render), milliseconds(timings.compare), milliseconds(timings.output));
+    }
+
+    let total_render: Duration = page_timings.iter().map(|timings| timings.render).sum();
+    let total_compare: Duration = page_timings.iter().map(|timings| timings.compare).sum();
+    let total_output: Duration = page_timings.iter().map(|timings| timings.output).sum::<Duration>() + saving;
+
+    debug!("{:>8} {:>12.1} {:>12.1} {:>12.1}", "total", milliseconds(total_render), milliseconds(total_compare), milliseconds(total_output));
+    debug!("(the output total includes {:.1} ms saving the output file)", milliseconds(saving));
+}